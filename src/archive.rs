@@ -0,0 +1,162 @@
+use crate::{Result, TodoList};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+impl TodoList {
+    /// 完了したタスクを done.txt に追記し、リストから取り除く
+    ///
+    /// 追記に失敗した場合、タスクはリストに残る。
+    /// 取り除いた結果を todo.txt に反映するには、続けて保存する必要がある。
+    /// done.txt への追記は取り消せないため、操作履歴は消去される。
+    /// 戻り値はアーカイブしたタスクの数。
+    pub fn archive_completed<P: AsRef<Path>>(&mut self, done_path: P) -> Result<usize> {
+        let lines: Vec<String> = self
+            .iter()
+            .filter(|todo| todo.completed)
+            .map(|todo| todo.to_string())
+            .collect();
+
+        if lines.is_empty() {
+            return Ok(0);
+        }
+
+        append_lines(done_path.as_ref(), &lines)?;
+
+        let archived = self.drain_where(|todo| todo.completed).len();
+        // 取り消すと done.txt に残ったタスクが todo.txt に戻り、重複してしまう
+        self.clear_history();
+
+        Ok(archived)
+    }
+
+    /// todo.txt と done.txt を読み込み、1 つのリストにまとめる
+    ///
+    /// 集計やレポート用。done.txt が存在しない場合は todo.txt のみを読み込む。
+    pub fn from_file_with_done<P, Q>(todo_path: P, done_path: Q) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let mut list = Self::from_file(todo_path)?;
+        // done.txt の内容を含むため、todo.txt との差分検出には使えない
+        list.clear_baseline();
+
+        let done = match fs::read_to_string(done_path) {
+            Ok(content) => Self::from_string(&content)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(list),
+            Err(e) => return Err(e.into()),
+        };
+        list.extend(done);
+
+        Ok(list)
+    }
+}
+
+/// ファイル末尾に行を追記する
+///
+/// 既存の内容が改行で終わっていない場合は、先に改行を補う。
+fn append_lines(path: &Path, lines: &[String]) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)?;
+
+    let mut content = String::new();
+    if file.metadata()?.len() > 0 {
+        let mut last = [0u8; 1];
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last)?;
+        if last[0] != b'\n' {
+            content.push('\n');
+        }
+    }
+
+    for line in lines {
+        content.push_str(line);
+        content.push('\n');
+    }
+
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Todo;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn test_archive_completed() {
+        let dir = temp_dir("archive");
+        let done_path = dir.join("done.txt");
+        fs::write(&done_path, "x 2024-11-01 Old task").unwrap();
+
+        let mut list =
+            TodoList::from_string("Task 1\nx 2024-11-03 Task 2\nTask 3\nx 2024-11-04 Task 4")
+                .unwrap();
+        assert_eq!(list.archive_completed(&done_path).unwrap(), 2);
+
+        assert_eq!(list.to_string(), "Task 1\nTask 3");
+        assert_eq!(
+            fs::read_to_string(&done_path).unwrap(),
+            "x 2024-11-01 Old task\nx 2024-11-03 Task 2\nx 2024-11-04 Task 4\n"
+        );
+
+        assert_eq!(list.archive_completed(&done_path).unwrap(), 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_archive_clears_history() {
+        let dir = temp_dir("archive-history");
+        let done_path = dir.join("done.txt");
+
+        let mut list = TodoList::from_string("Task 1\nx 2024-11-03 Task 2")
+            .unwrap()
+            .with_history(10);
+        list.add(Todo::new("Task 3"));
+        assert_eq!(list.archive_completed(&done_path).unwrap(), 1);
+
+        assert!(!list.can_undo());
+        assert!(!list.undo().unwrap());
+        assert_eq!(list.to_string(), "Task 1\nTask 3");
+        assert_eq!(
+            fs::read_to_string(&done_path).unwrap(),
+            "x 2024-11-03 Task 2\n"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_archive_failure_keeps_tasks() {
+        let dir = temp_dir("archive-failure");
+        let done_path = dir.join("missing").join("done.txt");
+
+        let mut list = TodoList::from_string("Task 1\nx 2024-11-03 Task 2").unwrap();
+        assert!(list.archive_completed(&done_path).is_err());
+        assert_eq!(list.len(), 2);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_from_file_with_done() {
+        let dir = temp_dir("with-done");
+        let todo_path = dir.join("todo.txt");
+        let done_path = dir.join("done.txt");
+        fs::write(&todo_path, "Task 1\n").unwrap();
+
+        let list = TodoList::from_file_with_done(&todo_path, &done_path).unwrap();
+        assert_eq!(list.len(), 1);
+
+        fs::write(&done_path, "x 2024-11-03 Task 2\n").unwrap();
+        let list = TodoList::from_file_with_done(&todo_path, &done_path).unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.completed().len(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,84 @@
+use chrono::{FixedOffset, Local, NaiveDate, Utc};
+use std::fmt;
+
+/// 「今日」の日付を提供するクロック
+///
+/// 完了日の記録や期限日・繰り返しの計算に使われる。
+/// テストやタイムゾーンの異なる利用者を扱うサービスでは、
+/// [`FixedClock`] や [`OffsetClock`] に差し替えることで日付を固定できる。
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// 今日の日付を取得
+    fn today(&self) -> NaiveDate;
+}
+
+/// システムのローカルタイムゾーンの日付を返すクロック
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn today(&self) -> NaiveDate {
+        Local::now().date_naive()
+    }
+}
+
+/// 常に同じ日付を返すクロック
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(NaiveDate);
+
+impl FixedClock {
+    /// 指定した日付を返すクロックを作成
+    pub fn new(date: NaiveDate) -> Self {
+        Self(date)
+    }
+}
+
+impl Clock for FixedClock {
+    fn today(&self) -> NaiveDate {
+        self.0
+    }
+}
+
+/// 指定した UTC オフセットでの日付を返すクロック
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OffsetClock(FixedOffset);
+
+impl OffsetClock {
+    /// 指定したオフセットのクロックを作成
+    pub fn new(offset: FixedOffset) -> Self {
+        Self(offset)
+    }
+
+    /// UTC から `hours` 時間進んだ（負の場合は遅れた）クロックを作成
+    ///
+    /// `hours` が ±24 時間の範囲外の場合は `None` を返す。
+    pub fn east_hours(hours: i32) -> Option<Self> {
+        FixedOffset::east_opt(hours.checked_mul(3600)?).map(Self)
+    }
+}
+
+impl Clock for OffsetClock {
+    fn today(&self) -> NaiveDate {
+        Utc::now().with_timezone(&self.0).date_naive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_clock() {
+        let date = NaiveDate::from_ymd_opt(2024, 11, 3).unwrap();
+        assert_eq!(FixedClock::new(date).today(), date);
+    }
+
+    #[test]
+    fn test_offset_clock() {
+        let tokyo = OffsetClock::east_hours(9).unwrap();
+        let honolulu = OffsetClock::east_hours(-10).unwrap();
+        let diff = tokyo.today() - honolulu.today();
+        assert!((0..=1).contains(&diff.num_days()));
+
+        assert!(OffsetClock::east_hours(25).is_none());
+    }
+}
@@ -0,0 +1,603 @@
+use crate::parser::parse_line;
+use crate::{LineEnding, Priority, Result, Todo, TodoError, TodoList};
+use chrono::NaiveDate;
+
+/// CSV の列に対応付ける `Todo` のフィールド
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvField {
+    /// 完了状態（`x`・`true`・`yes`・`1`・`done` を完了とみなす）
+    Completed,
+    /// 優先度 (`A` または `(A)`)
+    Priority,
+    /// 作成日
+    CreationDate,
+    /// 完了日
+    CompletionDate,
+    /// 説明文
+    Description,
+    /// プロジェクト（区切り文字で複数指定、先頭の `+` は省略可能）
+    Projects,
+    /// コンテキスト（区切り文字で複数指定、先頭の `@` は省略可能）
+    Contexts,
+    /// タグの値
+    Tag(String),
+    /// todo.txt の 1 行全体
+    Line,
+}
+
+/// CSV の列と `Todo` のフィールドの対応と書式
+///
+/// 読み込み時は 1 行目をヘッダーとして、見出しが一致する列（大文字小文字と前後の空白は無視）を対応付ける。
+/// 対応付けのない列は無視される。
+///
+/// ```
+/// use todo_rs::{CsvField, CsvMapping, TodoList};
+///
+/// let mapping = CsvMapping::new()
+///     .with_column("Task", CsvField::Description)
+///     .with_column("Tags", CsvField::Projects)
+///     .with_column("Deadline", CsvField::Tag("due".to_string()))
+///     .with_separator(";");
+///
+/// let csv = "Task,Tags,Deadline\n\"Write report, final\",Work;Q3,2024-03-01\n";
+/// let list = TodoList::from_csv(csv, &mapping).unwrap();
+/// assert_eq!(list.get(0).unwrap().to_string(), "Write report, final +Work +Q3 due:2024-03-01");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvMapping {
+    /// 見出しとフィールドの組（書き出し時の列の順序）
+    pub columns: Vec<(String, CsvField)>,
+
+    /// 列の区切り文字
+    pub delimiter: char,
+
+    /// プロジェクトなど複数の値を 1 つのセルに入れるときの区切り
+    pub separator: String,
+
+    /// 日付の書式 (`chrono` の書式指定)
+    pub date_format: String,
+
+    /// 書き出し時の改行コード
+    pub line_ending: LineEnding,
+}
+
+impl Default for CsvMapping {
+    /// 標準の列 (`completed`, `priority`, `creation_date`, `completion_date`,
+    /// `description`, `projects`, `contexts`) を持つ対応付け
+    fn default() -> Self {
+        Self::new()
+            .with_column("completed", CsvField::Completed)
+            .with_column("priority", CsvField::Priority)
+            .with_column("creation_date", CsvField::CreationDate)
+            .with_column("completion_date", CsvField::CompletionDate)
+            .with_column("description", CsvField::Description)
+            .with_column("projects", CsvField::Projects)
+            .with_column("contexts", CsvField::Contexts)
+    }
+}
+
+impl CsvMapping {
+    /// 列のない対応付けを作成
+    pub fn new() -> Self {
+        Self {
+            columns: Vec::new(),
+            delimiter: ',',
+            separator: " ".to_string(),
+            date_format: "%Y-%m-%d".to_string(),
+            line_ending: LineEnding::CrLf,
+        }
+    }
+
+    /// 列を追加
+    pub fn with_column(mut self, header: impl Into<String>, field: CsvField) -> Self {
+        self.columns.push((header.into(), field));
+        self
+    }
+
+    /// 列の区切り文字を設定
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// 複数の値の区切りを設定
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// 日付の書式を設定
+    pub fn with_date_format(mut self, date_format: impl Into<String>) -> Self {
+        self.date_format = date_format.into();
+        self
+    }
+
+    /// 書き出し時の改行コードを設定
+    pub fn with_line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    fn field_for(&self, header: &str) -> Option<&CsvField> {
+        let header = header.trim();
+        self.columns
+            .iter()
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case(header))
+            .map(|(_, field)| field)
+    }
+
+    fn format_date(&self, date: Option<NaiveDate>) -> String {
+        date.map(|date| date.format(&self.date_format).to_string())
+            .unwrap_or_default()
+    }
+
+    fn cell(&self, todo: &Todo, field: &CsvField) -> String {
+        match field {
+            CsvField::Completed => if todo.completed { "x" } else { "" }.to_string(),
+            CsvField::Priority => todo
+                .priority
+                .map(|p| p.as_char().to_string())
+                .unwrap_or_default(),
+            CsvField::CreationDate => self.format_date(todo.creation_date),
+            CsvField::CompletionDate => self.format_date(todo.completion_date),
+            CsvField::Description => todo.description.clone(),
+            CsvField::Projects => todo.projects.join(&self.separator),
+            CsvField::Contexts => todo.contexts.join(&self.separator),
+            CsvField::Tag(key) => todo.get_tag(key).cloned().unwrap_or_default(),
+            CsvField::Line => todo.to_string(),
+        }
+    }
+}
+
+impl TodoList {
+    /// CSV として出力（1 行目はヘッダー）
+    pub fn to_csv(&self, mapping: &CsvMapping) -> String {
+        let newline = mapping.line_ending.as_str();
+        let row = |cells: Vec<String>| {
+            let cells: Vec<String> = cells
+                .iter()
+                .map(|cell| escape_field(cell, mapping.delimiter))
+                .collect();
+            cells.join(&mapping.delimiter.to_string()) + newline
+        };
+
+        let mut out = row(mapping
+            .columns
+            .iter()
+            .map(|(header, _)| header.clone())
+            .collect());
+        for todo in self.iter() {
+            out.push_str(&row(mapping
+                .columns
+                .iter()
+                .map(|(_, field)| mapping.cell(todo, field))
+                .collect()));
+        }
+        out
+    }
+
+    /// CSV から読み込む（1 行目はヘッダー）
+    ///
+    /// 空の行は無視する。読めない値があった場合は、その行と列を含む
+    /// [`TodoError::Csv`] を返す（行はヘッダーを 1 行目として数える）。
+    /// 説明文の列に `+Project` や `key:value` のような語、先頭の `x ` や `(A)`、日付など、
+    /// todo.txt の行として保存すると別のフィールドとして読まれるものがある場合もエラーになる。
+    /// 説明文は、プロジェクトかコンテキストがあれば空でもよい。
+    pub fn from_csv(content: &str, mapping: &CsvMapping) -> Result<TodoList> {
+        let mut records = read_records(content, mapping.delimiter)?.into_iter();
+        let Some((_, header)) = records.next() else {
+            return Ok(TodoList::new());
+        };
+
+        let fields: Vec<Option<&CsvField>> = header.iter().map(|h| mapping.field_for(h)).collect();
+        if !fields
+            .iter()
+            .any(|f| matches!(f, Some(CsvField::Description | CsvField::Line)))
+        {
+            return Err(csv_error(
+                1,
+                1,
+                "説明文か todo.txt の行に対応する列がありません",
+            ));
+        }
+
+        let mut list = TodoList::new();
+        for (row, cells) in records {
+            if cells.iter().all(|cell| cell.trim().is_empty()) {
+                continue;
+            }
+            list.add(build_todo(row, &cells, &fields, mapping)?);
+        }
+        Ok(list)
+    }
+}
+
+/// 1 行分のセルから `Todo` を組み立てる
+fn build_todo(
+    row: usize,
+    cells: &[String],
+    fields: &[Option<&CsvField>],
+    mapping: &CsvMapping,
+) -> Result<Todo> {
+    let cell_of = |wanted: fn(&CsvField) -> bool| {
+        fields
+            .iter()
+            .position(|f| f.is_some_and(wanted))
+            .map(|i| (i + 1, cells.get(i).map(|c| c.trim()).unwrap_or("")))
+    };
+
+    // 行全体の列を土台にし、説明文の列があればそちらを優先する
+    let mut todo = match cell_of(|f| *f == CsvField::Line) {
+        Some((column, line)) if !line.is_empty() => {
+            parse_line(line).map_err(|kind| csv_error(row, column, kind.to_string()))?
+        }
+        _ => Todo::new(""),
+    };
+    let description_column = cell_of(|f| *f == CsvField::Description).map(|(column, _)| column);
+    if let Some((column, description)) = cell_of(|f| *f == CsvField::Description)
+        && !description.is_empty()
+    {
+        // 複数行のセルも todo.txt の 1 行に収める
+        let description = description.split_whitespace().collect::<Vec<_>>().join(" ");
+        // `+word` や `key:value`、先頭の `x ` や `(A)` などは、保存して読み直すと別のフィールドになる
+        if !parse_line(&description).is_ok_and(|parsed| parsed == Todo::new(description.as_str())) {
+            return Err(csv_error(
+                row,
+                column,
+                format!(
+                    "説明文が todo.txt の説明文以外のフィールドとして読まれます: {}",
+                    description
+                ),
+            ));
+        }
+        todo.description = description;
+    }
+
+    for (i, field) in fields.iter().enumerate() {
+        let (Some(field), Some(value)) = (field, cells.get(i)) else {
+            continue;
+        };
+        let value = value.trim();
+        let column = i + 1;
+        let error = |message: String| csv_error(row, column, message);
+        if value.is_empty() {
+            continue;
+        }
+
+        match field {
+            CsvField::Completed => {
+                todo.completed = match value.to_ascii_lowercase().as_str() {
+                    "x" | "true" | "yes" | "1" | "done" => true,
+                    "false" | "no" | "0" => false,
+                    _ => return Err(error(format!("完了状態として読めません: {}", value))),
+                }
+            }
+            CsvField::Priority => {
+                let letter = value
+                    .strip_prefix('(')
+                    .and_then(|v| v.strip_suffix(')'))
+                    .unwrap_or(value);
+                let mut chars = letter.chars();
+                todo.priority = match (chars.next(), chars.next()) {
+                    (Some(c), None) => Priority::new(c.to_ascii_uppercase()),
+                    _ => None,
+                };
+                if todo.priority.is_none() {
+                    return Err(error(format!(
+                        "優先度は A-Z である必要があります: {}",
+                        value
+                    )));
+                }
+            }
+            CsvField::CreationDate | CsvField::CompletionDate => {
+                let date = NaiveDate::parse_from_str(value, &mapping.date_format)
+                    .map_err(|_| error(format!("日付として読めません: {}", value)))?;
+                if **field == CsvField::CreationDate {
+                    todo.creation_date = Some(date);
+                } else {
+                    todo.completion_date = Some(date);
+                }
+            }
+            CsvField::Projects | CsvField::Contexts => {
+                let prefix = if **field == CsvField::Projects {
+                    '+'
+                } else {
+                    '@'
+                };
+                for item in value.split(mapping.separator.as_str()) {
+                    let item = item.trim();
+                    let item = item.strip_prefix(prefix).unwrap_or(item);
+                    if item.is_empty() {
+                        continue;
+                    }
+                    if item.contains(char::is_whitespace) {
+                        return Err(error(format!("空白を含む値は使えません: {}", item)));
+                    }
+                    if prefix == '+' && !todo.has_project(item) {
+                        todo.add_project(item);
+                    } else if prefix == '@' && !todo.has_context(item) {
+                        todo.add_context(item);
+                    }
+                }
+            }
+            CsvField::Tag(key) => {
+                if value.contains(char::is_whitespace) {
+                    return Err(error(format!("タグの値に空白は使えません: {}", value)));
+                }
+                todo.add_tag(key.as_str(), value);
+            }
+            CsvField::Description | CsvField::Line => {}
+        }
+    }
+
+    todo.normalize_completion();
+
+    // todo.txt と同様、プロジェクトかコンテキストがあれば説明文は空でもよい
+    if todo.description.is_empty() && todo.projects.is_empty() && todo.contexts.is_empty() {
+        return Err(csv_error(
+            row,
+            description_column.unwrap_or(1),
+            "説明文が空です",
+        ));
+    }
+    Ok(todo)
+}
+
+fn csv_error(row: usize, column: usize, message: impl Into<String>) -> TodoError {
+    TodoError::Csv {
+        row,
+        column,
+        message: message.into(),
+    }
+}
+
+/// CSV のフィールドを必要に応じて引用符で囲む
+pub(crate) fn escape_field(value: &str, delimiter: char) -> String {
+    let needs_quotes = value.contains([delimiter, '"', '\n', '\r'])
+        || value.starts_with(' ')
+        || value.ends_with(' ');
+    if needs_quotes {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// RFC 4180 形式の CSV をレコード（行番号とフィールド）に分ける
+///
+/// 引用符内の改行・区切り文字・`""` に対応する。行番号は 1 始まりのレコード番号。
+fn read_records(content: &str, delimiter: char) -> Result<Vec<(usize, Vec<String>)>> {
+    let content = content.strip_prefix('\u{FEFF}').unwrap_or(content);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut row = 1;
+    let mut chars = content.chars().peekable();
+
+    // 現在のフィールドが引用符で始まったか
+    let mut at_field_start = true;
+
+    while let Some(c) = chars.next() {
+        if at_field_start && c == '"' {
+            let column = record.len() + 1;
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err(csv_error(row, column, "引用符が閉じられていません")),
+                }
+            }
+            match chars.peek() {
+                None | Some('\n' | '\r') => {}
+                Some(&c) if c == delimiter => {}
+                Some(_) => {
+                    return Err(csv_error(row, column, "引用符の後に余分な文字があります"));
+                }
+            }
+            at_field_start = false;
+            continue;
+        }
+
+        at_field_start = false;
+        match c {
+            c if c == delimiter => {
+                record.push(std::mem::take(&mut field));
+                at_field_start = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                record.push(std::mem::take(&mut field));
+                records.push((row, std::mem::take(&mut record)));
+                row += 1;
+                at_field_start = true;
+            }
+            c => field.push(c),
+        }
+    }
+
+    if !at_field_start || !record.is_empty() || !field.is_empty() {
+        record.push(field);
+        records.push((row, record));
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(content: &str) -> Vec<Vec<String>> {
+        read_records(content, ',')
+            .unwrap()
+            .into_iter()
+            .map(|(_, record)| record)
+            .collect()
+    }
+
+    fn error_position(result: Result<TodoList>) -> (usize, usize) {
+        match result {
+            Err(TodoError::Csv { row, column, .. }) => (row, column),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_read_records() {
+        assert_eq!(
+            records("a,b\r\n\"c,d\",\"say \"\"hi\"\"\"\n\"multi\nline\",\n"),
+            [
+                vec!["a", "b"],
+                vec!["c,d", "say \"hi\""],
+                vec!["multi\nline", ""],
+            ]
+        );
+        assert_eq!(records("\u{FEFF}a,\"\"\nb"), [vec!["a", ""], vec!["b"]]);
+        assert!(records("").is_empty());
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let error = |content: &str| match read_records(content, ',') {
+            Err(TodoError::Csv { row, column, .. }) => (row, column),
+            other => panic!("unexpected result: {other:?}"),
+        };
+        assert_eq!(error("a,b\nc,\"open"), (2, 2));
+        assert_eq!(error("\"a\"b,c"), (1, 1));
+    }
+
+    #[test]
+    fn test_escape_field() {
+        assert_eq!(escape_field("plain", ','), "plain");
+        assert_eq!(escape_field("a,b", ','), "\"a,b\"");
+        assert_eq!(escape_field("a,b", ';'), "a,b");
+        assert_eq!(escape_field("say \"hi\"", ','), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_field(" padded", ','), "\" padded\"");
+    }
+
+    #[test]
+    fn test_default_round_trip() {
+        let content = "\
+(A) 2024-01-15 Call mom +Family @phone
+x 2024-01-20 2024-01-10 Write report +Work +Q1 @office
+Buy milk";
+        let list = TodoList::from_string(content).unwrap();
+        let csv = list.to_csv(&CsvMapping::default());
+        assert!(csv.starts_with(
+            "completed,priority,creation_date,completion_date,description,projects,contexts\r\n"
+        ));
+        assert!(csv.contains("x,,2024-01-10,2024-01-20,Write report,Work Q1,office\r\n"));
+
+        let back = TodoList::from_csv(&csv, &CsvMapping::default()).unwrap();
+        assert_eq!(back.to_string(), content);
+    }
+
+    #[test]
+    fn test_custom_mapping() {
+        let mapping = CsvMapping::new()
+            .with_column("Done", CsvField::Completed)
+            .with_column("Task", CsvField::Description)
+            .with_column("Where", CsvField::Contexts)
+            .with_column("Due", CsvField::Tag("due".to_string()))
+            .with_column("Started", CsvField::CreationDate)
+            .with_delimiter(';')
+            .with_separator("|")
+            .with_date_format("%d/%m/%Y")
+            .with_line_ending(LineEnding::Lf);
+
+        let csv = "task;DONE;where;due;started;ignored\n\
+                   Call mom;no;@phone|home;2024-03-01;15/01/2024;x\n\
+                   ;;;;;\n\
+                   \"Pay; rent\";yes;;;;\n";
+        let list = TodoList::from_csv(csv, &mapping).unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(
+            list.get(0).unwrap().to_string(),
+            "2024-01-15 Call mom @phone @home due:2024-03-01"
+        );
+        assert_eq!(list.get(1).unwrap().to_string(), "x Pay; rent");
+
+        assert_eq!(
+            list.to_csv(&mapping),
+            "Done;Task;Where;Due;Started\n\
+             ;Call mom;phone|home;2024-03-01;15/01/2024\n\
+             x;\"Pay; rent\";;;\n"
+        );
+    }
+
+    #[test]
+    fn test_line_column() {
+        let mapping = CsvMapping::new()
+            .with_column("line", CsvField::Line)
+            .with_column("owner", CsvField::Tag("owner".to_string()));
+        let list = TodoList::from_csv("line,owner\n(B) Fix bug +App,alice\n", &mapping).unwrap();
+        assert_eq!(
+            list.get(0).unwrap().to_string(),
+            "(B) Fix bug +App owner:alice"
+        );
+        assert_eq!(
+            list.to_csv(&mapping),
+            "line,owner\r\n(B) Fix bug +App owner:alice,alice\r\n"
+        );
+    }
+
+    #[test]
+    fn test_multiline_description() {
+        let csv = "description,projects\n\"multi\nline  text\",Work\nSecond,\n";
+        let list = TodoList::from_csv(csv, &CsvMapping::default()).unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.get(0).unwrap().description, "multi line text");
+
+        let reparsed = TodoList::from_string(&list.to_string()).unwrap();
+        assert_eq!(reparsed.len(), 2);
+    }
+
+    #[test]
+    fn test_description_that_reads_as_other_fields() {
+        let mapping = CsvMapping::default();
+        for description in [
+            "Pay +Rent",
+            "Call @phone",
+            "Pay due:2024-01-01",
+            "x done",
+            "(A) urgent",
+            "2024-01-01 dated",
+        ] {
+            let csv = format!("description\n{}\n", description);
+            assert_eq!(
+                error_position(TodoList::from_csv(&csv, &mapping)),
+                (2, 1),
+                "{description}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_description_with_projects() {
+        let list = TodoList::from_string("+Work @home").unwrap();
+        let mapping = CsvMapping::default();
+        let back = TodoList::from_csv(&list.to_csv(&mapping), &mapping).unwrap();
+        assert_eq!(back.to_string(), "+Work @home");
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let mapping = CsvMapping::default().with_column("est", CsvField::Tag("est".to_string()));
+        let header = "description,priority,creation_date,completed,projects,est\n";
+        let import = |rows: &str| TodoList::from_csv(&format!("{header}{rows}"), &mapping);
+
+        assert_eq!(error_position(import("ok,,,,,\nbad,AB,,,,\n")), (3, 2));
+        assert_eq!(error_position(import("bad,,2024-13-01,,,\n")), (2, 3));
+        assert_eq!(error_position(import("bad,,,maybe,,\n")), (2, 4));
+        assert_eq!(error_position(import("bad,,,,My\tProject,\n")), (2, 5));
+        assert_eq!(error_position(import("bad,,,,,two words\n")), (2, 6));
+        assert_eq!(error_position(import(",A,,,,\n")), (2, 1));
+        assert_eq!(
+            error_position(TodoList::from_csv("title\nx\n", &mapping)),
+            (1, 1)
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{Result, Todo, TodoError, TodoList};
+use std::collections::BTreeSet;
+
+/// 依存するタスクの ID を表すタグのキー
+const DEPENDENCY_KEY: &str = "dep";
+
+/// このタスクの完了を待つタスクの ID を表すタグのキー
+const BLOCKS_KEY: &str = "blocks";
+
+/// カンマ区切りのタグの値を分割する
+fn split_ids(value: Option<&String>) -> Vec<&str> {
+    value
+        .map(|value| value.split(',').filter(|id| !id.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+impl Todo {
+    /// このタスクが依存するタスクの ID (`dep:` タグ、カンマ区切り) を取得
+    pub fn dependencies(&self) -> Vec<&str> {
+        split_ids(self.tags.get(DEPENDENCY_KEY))
+    }
+
+    /// このタスクの完了を待つタスクの ID (`blocks:` タグ、カンマ区切り) を取得
+    pub fn blocks(&self) -> Vec<&str> {
+        split_ids(self.tags.get(BLOCKS_KEY))
+    }
+
+    /// 依存するタスクの ID を追加
+    pub fn add_dependency(&mut self, id: impl Into<String>) {
+        let id = id.into();
+        if self.dependencies().contains(&id.as_str()) {
+            return;
+        }
+        let value = match self.tags.get(DEPENDENCY_KEY) {
+            Some(current) if !current.is_empty() => format!("{},{}", current, id),
+            _ => id,
+        };
+        self.add_tag(DEPENDENCY_KEY, value);
+    }
+}
+
+/// タスク間の依存関係（インデックス間の辺）
+struct DependencyGraph {
+    /// `blockers[i]` は `i` 番目のタスクより先に完了する必要があるタスク
+    blockers: Vec<BTreeSet<usize>>,
+}
+
+impl DependencyGraph {
+    fn build(list: &TodoList) -> Result<Self> {
+        let (by_id, duplicate) = list.persistent_id_map();
+        if let Some(id) = duplicate {
+            return Err(TodoError::DuplicateTaskId(id.to_string()));
+        }
+
+        let lookup = |index: usize, reference: &str| {
+            by_id
+                .get(reference)
+                .copied()
+                .ok_or_else(|| TodoError::DanglingDependency {
+                    index,
+                    reference: reference.to_string(),
+                })
+        };
+
+        let mut blockers = vec![BTreeSet::new(); list.len()];
+        for (index, todo) in list.iter().enumerate() {
+            for reference in todo.dependencies() {
+                blockers[index].insert(lookup(index, reference)?);
+            }
+            for reference in todo.blocks() {
+                blockers[lookup(index, reference)?].insert(index);
+            }
+        }
+
+        Ok(Self { blockers })
+    }
+
+    /// 未完了の先行タスクがあるかチェック
+    fn is_blocked(&self, list: &TodoList, index: usize) -> bool {
+        self.blockers[index]
+            .iter()
+            .any(|&blocker| list.get(blocker).is_some_and(|todo| !todo.completed))
+    }
+}
+
+impl TodoList {
+    /// 依存関係を検証する
+    ///
+    /// `id:` の重複、存在しない ID の参照、循環がある場合はエラーを返す。
+    pub fn check_dependencies(&self) -> Result<()> {
+        self.topological_order().map(|_| ())
+    }
+
+    /// 未完了の先行タスク（`dep:` / `blocks:` で指定されたもの）があるタスクを取得
+    ///
+    /// 完了済みのタスクは含まれない。
+    pub fn blocked(&self) -> Result<Vec<&Todo>> {
+        let graph = DependencyGraph::build(self)?;
+        Ok(self
+            .iter()
+            .enumerate()
+            .filter(|&(i, todo)| !todo.completed && graph.is_blocked(self, i))
+            .map(|(_, todo)| todo)
+            .collect())
+    }
+
+    /// 先行タスクがすべて完了していて、着手できる未完了タスクを取得
+    pub fn ready(&self) -> Result<Vec<&Todo>> {
+        let graph = DependencyGraph::build(self)?;
+        Ok(self
+            .iter()
+            .enumerate()
+            .filter(|&(i, todo)| !todo.completed && !graph.is_blocked(self, i))
+            .map(|(_, todo)| todo)
+            .collect())
+    }
+
+    /// インデックスのタスクの未完了の先行タスクを取得
+    pub fn blockers_of(&self, index: usize) -> Result<Vec<&Todo>> {
+        if index >= self.len() {
+            return Err(TodoError::IndexOutOfBounds(index));
+        }
+        let graph = DependencyGraph::build(self)?;
+        Ok(graph.blockers[index]
+            .iter()
+            .filter_map(|&blocker| self.get(blocker))
+            .filter(|todo| !todo.completed)
+            .collect())
+    }
+
+    /// 先行タスクが先に来るように並べたインデックスを取得
+    ///
+    /// 依存関係のないタスク同士は元の順序を保つ。
+    /// 循環がある場合は [`TodoError::DependencyCycle`] を返す。
+    pub fn topological_order(&self) -> Result<Vec<usize>> {
+        let graph = DependencyGraph::build(self)?;
+
+        let mut dependents = vec![Vec::new(); self.len()];
+        let mut remaining: Vec<usize> = graph.blockers.iter().map(BTreeSet::len).collect();
+        for (index, blockers) in graph.blockers.iter().enumerate() {
+            for &blocker in blockers {
+                dependents[blocker].push(index);
+            }
+        }
+
+        let mut available: BTreeSet<usize> =
+            (0..self.len()).filter(|&i| remaining[i] == 0).collect();
+        let mut order = Vec::with_capacity(self.len());
+
+        while let Some(index) = available.pop_first() {
+            order.push(index);
+            for &dependent in &dependents[index] {
+                remaining[dependent] -= 1;
+                if remaining[dependent] == 0 {
+                    available.insert(dependent);
+                }
+            }
+        }
+
+        if order.len() < self.len() {
+            let start = (0..self.len()).find(|&i| remaining[i] > 0).unwrap();
+            return Err(TodoError::DependencyCycle(find_cycle(
+                &graph, &remaining, start,
+            )));
+        }
+
+        Ok(order)
+    }
+}
+
+/// 未解決のタスクから先行タスクをたどり、循環を構成するインデックスを求める
+fn find_cycle(graph: &DependencyGraph, remaining: &[usize], start: usize) -> Vec<usize> {
+    let mut path = vec![start];
+    let mut current = start;
+
+    loop {
+        // 未解決のタスクには必ず未解決の先行タスクがある
+        current = *graph.blockers[current]
+            .iter()
+            .find(|&&blocker| remaining[blocker] > 0)
+            .unwrap();
+
+        if let Some(pos) = path.iter().position(|&i| i == current) {
+            let mut cycle = path.split_off(pos);
+            // 先行タスクから順に並べる
+            cycle.reverse();
+            return cycle;
+        }
+        path.push(current);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptions(todos: Vec<&Todo>) -> Vec<&str> {
+        todos
+            .into_iter()
+            .map(|todo| todo.description.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_dependency_tags() {
+        let mut todo: Todo = "Deploy dep:1,2 blocks:9".parse().unwrap();
+        assert_eq!(todo.dependencies(), ["1", "2"]);
+        assert_eq!(todo.blocks(), ["9"]);
+
+        todo.add_dependency("3");
+        todo.add_dependency("1");
+        assert_eq!(todo.to_string(), "Deploy dep:1,2,3 blocks:9");
+    }
+
+    #[test]
+    fn test_blocked_and_ready() {
+        let content = "Write code id:1\nReview id:2 dep:1\nDeploy dep:2\nPlan blocks:1";
+        let mut list = TodoList::from_string(content).unwrap();
+
+        assert_eq!(descriptions(list.ready().unwrap()), ["Plan"]);
+        assert_eq!(
+            descriptions(list.blocked().unwrap()),
+            ["Write code", "Review", "Deploy"]
+        );
+
+        list.complete(3).unwrap();
+        assert_eq!(descriptions(list.ready().unwrap()), ["Write code"]);
+
+        list.complete(0).unwrap();
+        assert_eq!(descriptions(list.ready().unwrap()), ["Review"]);
+        assert_eq!(descriptions(list.blockers_of(2).unwrap()), ["Review"]);
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let content = "Deploy dep:b\nTest id:b dep:a\nSetup\nBuild id:a";
+        let list = TodoList::from_string(content).unwrap();
+        assert_eq!(list.topological_order().unwrap(), [2, 3, 1, 0]);
+        assert!(list.check_dependencies().is_ok());
+    }
+
+    #[test]
+    fn test_dependency_cycle() {
+        let content = "Other\nA id:a dep:c\nB id:b dep:a\nC id:c dep:b";
+        let list = TodoList::from_string(content).unwrap();
+
+        match list.topological_order() {
+            Err(TodoError::DependencyCycle(cycle)) => {
+                assert_eq!(cycle.len(), 3);
+                let mut sorted = cycle.clone();
+                sorted.sort();
+                assert_eq!(sorted, [1, 2, 3]);
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_dangling_and_duplicate_ids() {
+        let list = TodoList::from_string("A id:a\nB dep:zzz").unwrap();
+        assert!(matches!(
+            list.ready(),
+            Err(TodoError::DanglingDependency { index: 1, reference }) if reference == "zzz"
+        ));
+
+        let list = TodoList::from_string("A id:a\nB id:a").unwrap();
+        assert!(matches!(
+            list.check_dependencies(),
+            Err(TodoError::DuplicateTaskId(id)) if id == "a"
+        ));
+    }
+}
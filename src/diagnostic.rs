@@ -0,0 +1,89 @@
+use crate::list::TodoList;
+use std::fmt;
+use std::ops::Range;
+
+/// 行のパースに失敗したときの扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParsePolicy {
+    /// 最初に失敗した行でエラーを返す
+    Strict,
+    /// 失敗した行を読み飛ばし、診断情報として収集する
+    #[default]
+    Lenient,
+    /// 診断情報を収集し、失敗した行はそのままリストに保持する
+    ///
+    /// 保持された行は保存時に元の位置へそのまま書き戻される。
+    Preserve,
+}
+
+/// 行のパースに失敗した理由
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// 空の行
+    EmptyLine,
+    /// 完了マーカーや日付以外の内容がない
+    EmptyContent,
+    /// JSON Lines などのレコードとして読めない
+    InvalidRecord(String),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::EmptyLine => write!(f, "空の行はパースできません"),
+            ParseErrorKind::EmptyContent => write!(f, "タスクの内容が空です"),
+            ParseErrorKind::InvalidRecord(message) => write!(f, "無効なレコードです ({})", message),
+        }
+    }
+}
+
+/// パースに失敗した行の診断情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDiagnostic {
+    /// 行番号 (1 始まり)
+    pub line: usize,
+
+    /// 行内の開始列 (1 始まり、文字単位)
+    pub column: usize,
+
+    /// 入力全体における該当箇所のバイト範囲
+    pub span: Range<usize>,
+
+    /// 該当行の元のテキスト
+    pub raw: String,
+
+    /// 失敗の理由
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}行目 {}列目: {}: {}",
+            self.line, self.column, self.kind, self.raw
+        )
+    }
+}
+
+/// パース結果と診断情報
+#[derive(Debug, Clone, Default)]
+pub struct ParseReport {
+    /// パースできたタスクのリスト
+    pub list: TodoList,
+
+    /// パースに失敗した行の診断情報
+    pub diagnostics: Vec<ParseDiagnostic>,
+}
+
+impl ParseReport {
+    /// パースに失敗した行がないかチェック
+    pub fn is_clean(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// リストを取り出す
+    pub fn into_list(self) -> TodoList {
+        self.list
+    }
+}
@@ -1,3 +1,5 @@
+use crate::diagnostic::ParseDiagnostic;
+use crate::merge::MergeConflict;
 use thiserror::Error;
 
 /// Todo.txt 操作時のエラー型
@@ -12,11 +14,55 @@ pub enum TodoError {
     #[error("無効な優先度: {0}")]
     InvalidPriority(String),
 
+    #[error("無効な繰り返し指定: {0}")]
+    InvalidRecurrence(String),
+
     #[error("ファイル操作中にエラーが発生しました: {0}")]
     IoError(#[from] std::io::Error),
 
+    #[error("ファイルがロックされています: {}", .0.display())]
+    LockTimeout(std::path::PathBuf),
+
     #[error("指定されたインデックスが見つかりません: {0}")]
     IndexOutOfBounds(usize),
+
+    #[error("指定された ID のタスクが見つかりません: {0}")]
+    TaskNotFound(crate::id::TaskId),
+
+    #[error("行のパースに失敗しました: {0}")]
+    InvalidLine(ParseDiagnostic),
+
+    #[error("ファイルが外部で変更されており、{}件の競合を解決できません", .0.len())]
+    MergeConflict(Vec<MergeConflict>),
+
+    #[error("ID が重複しています: {0}")]
+    DuplicateTaskId(String),
+
+    #[error("{index}番目のタスクが存在しない ID を参照しています: {reference}")]
+    DanglingDependency { index: usize, reference: String },
+
+    #[error("依存関係が循環しています: {}", .0.iter().map(usize::to_string).collect::<Vec<_>>().join(" -> "))]
+    DependencyCycle(Vec<usize>),
+
+    #[error("クエリの{column}文字目: {message}")]
+    InvalidQuery { column: usize, message: String },
+
+    #[error("無効なソート指定: {0}")]
+    InvalidSortSpec(String),
+
+    #[error("CSV の{row}行目 {column}列目: {message}")]
+    Csv {
+        row: usize,
+        column: usize,
+        message: String,
+    },
+
+    #[error("{format} の{line}行目: {message}")]
+    Import {
+        format: &'static str,
+        line: usize,
+        message: String,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, TodoError>;
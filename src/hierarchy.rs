@@ -0,0 +1,331 @@
+use crate::history::Operation;
+use crate::{Result, Todo, TodoError, TodoList};
+use std::collections::HashMap;
+
+/// 親タスクの ID を表すタグのキー
+const PARENT_KEY: &str = "parent";
+
+impl Todo {
+    /// 親タスクの ID (`parent:` タグ) を取得
+    pub fn parent_id(&self) -> Option<&str> {
+        self.tags.get(PARENT_KEY).map(String::as_str)
+    }
+
+    /// 親タスクの ID を設定
+    pub fn set_parent_id(&mut self, id: impl Into<String>) {
+        self.add_tag(PARENT_KEY, id);
+    }
+}
+
+/// ツリー表示用のノード
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeNode<'a> {
+    /// ルートを 0 とした深さ
+    pub depth: usize,
+    /// リスト内のインデックス
+    pub index: usize,
+    /// タスク
+    pub todo: &'a Todo,
+}
+
+impl TodoList {
+    /// 親タスクのインデックスを取得
+    ///
+    /// `parent:` が存在しない ID や、複数のタスクが持つ ID を参照している場合は `None`
+    /// （依存関係と同様、重複した ID はどのタスクも指さない）。
+    pub fn parent_of(&self, index: usize) -> Option<usize> {
+        self.parent_map().get(index).copied().flatten()
+    }
+
+    /// 子タスクのインデックスを取得
+    pub fn children(&self, index: usize) -> Vec<usize> {
+        self.child_map().remove(&index).unwrap_or_default()
+    }
+
+    /// 祖先タスクのインデックスを近い順に取得
+    ///
+    /// 親子関係が循環している場合は、一巡したところで打ち切る。
+    pub fn ancestors(&self, index: usize) -> Vec<usize> {
+        let parents = self.parent_map();
+        let mut ancestors = Vec::new();
+        let mut current = index;
+        while let Some(parent) = parents.get(current).copied().flatten() {
+            if parent == index || ancestors.contains(&parent) {
+                break;
+            }
+            ancestors.push(parent);
+            current = parent;
+        }
+        ancestors
+    }
+
+    /// 子孫タスクのインデックスを深さ優先順で取得
+    pub fn descendants(&self, index: usize) -> Vec<usize> {
+        self.subtree(index, &self.child_map())
+            .into_iter()
+            .skip(1)
+            .map(|(_, index)| index)
+            .collect()
+    }
+
+    /// 子孫を含めた完了率（0.0〜100.0）を取得
+    ///
+    /// 子タスクがない場合は自身の完了状態で 0.0 か 100.0 になる。
+    /// 子タスクがある場合は子孫のうち完了しているものの割合。
+    pub fn completion_percentage(&self, index: usize) -> Result<f64> {
+        let todo = self.get(index).ok_or(TodoError::IndexOutOfBounds(index))?;
+        let descendants = self.descendants(index);
+
+        if descendants.is_empty() {
+            return Ok(if todo.completed { 100.0 } else { 0.0 });
+        }
+
+        let done = descendants
+            .iter()
+            .filter(|&&i| self.all()[i].completed)
+            .count();
+        Ok(done as f64 * 100.0 / descendants.len() as f64)
+    }
+
+    /// タスクとその子孫をまとめて完了にする
+    ///
+    /// 完了にしたタスクのインデックスを返す。
+    /// 繰り返しタスクの次回分はリストの末尾に追加される。
+    /// 操作履歴が有効な場合、全体が 1 つの操作として記録される。
+    pub fn complete_cascade(&mut self, index: usize) -> Result<Vec<usize>> {
+        if index >= self.len() {
+            return Err(TodoError::IndexOutOfBounds(index));
+        }
+        self.flush_pending();
+
+        let mut targets = vec![index];
+        targets.extend(self.descendants(index));
+
+        let mut operations = Vec::new();
+        let mut completed = Vec::new();
+        let mut result = Ok(());
+        for target in targets {
+            if self.all()[target].completed {
+                continue;
+            }
+            // 次回分は末尾に追加されるので、既存のインデックスはずれない
+            if let Err(e) = self.complete_entry(target, &mut operations) {
+                result = Err(e);
+                break;
+            }
+            completed.push(target);
+        }
+
+        // 途中で失敗した場合も、それまでに完了にした分は記録する
+        if !operations.is_empty() {
+            self.history_mut().record(Operation::Batch(operations));
+        }
+        result.map(|()| completed)
+    }
+
+    /// `parent:` で親を特定できないタスクのインデックスを取得
+    ///
+    /// 存在しない ID と、複数のタスクが持つ ID を参照しているタスクが含まれる。
+    pub fn orphans(&self) -> Vec<usize> {
+        let parents = self.parent_map();
+        self.iter()
+            .enumerate()
+            .filter(|&(index, todo)| todo.parent_id().is_some() && parents[index].is_none())
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// 親子関係に沿って深さ優先でタスクを列挙
+    ///
+    /// 親のないタスクと親が見つからないタスクをルートとして、元の順序で並べる。
+    /// 親子関係が循環しているタスクも、最初に現れたものをルートとして必ず列挙される。
+    pub fn tree(&self) -> impl Iterator<Item = TreeNode<'_>> {
+        let parents = self.parent_map();
+        let children = child_map(&parents);
+        let mut visited = vec![false; self.len()];
+        let mut nodes = Vec::with_capacity(self.len());
+
+        let roots = (0..self.len()).filter(|&i| parents[i].is_none());
+        let rest = 0..self.len();
+        for root in roots.chain(rest) {
+            if visited[root] {
+                continue;
+            }
+            for (depth, index) in self.subtree(root, &children) {
+                if !visited[index] {
+                    visited[index] = true;
+                    nodes.push(TreeNode {
+                        depth,
+                        index,
+                        todo: &self.all()[index],
+                    });
+                }
+            }
+        }
+
+        nodes.into_iter()
+    }
+
+    /// 各タスクの親のインデックスを求める
+    fn parent_map(&self) -> Vec<Option<usize>> {
+        let (by_id, _) = self.persistent_id_map();
+        self.iter()
+            .map(|todo| todo.parent_id().and_then(|id| by_id.get(id)).copied())
+            .collect()
+    }
+
+    /// 親のインデックスから子のインデックスへの対応表を作る
+    fn child_map(&self) -> HashMap<usize, Vec<usize>> {
+        child_map(&self.parent_map())
+    }
+
+    /// `root` 以下を深さ優先で (深さ, インデックス) として列挙（循環は一度だけたどる）
+    fn subtree(&self, root: usize, children: &HashMap<usize, Vec<usize>>) -> Vec<(usize, usize)> {
+        let mut seen = vec![false; self.len()];
+        let mut result = Vec::new();
+        let mut stack = vec![(0, root)];
+
+        while let Some((depth, index)) = stack.pop() {
+            if std::mem::replace(&mut seen[index], true) {
+                continue;
+            }
+            result.push((depth, index));
+            if let Some(kids) = children.get(&index) {
+                stack.extend(kids.iter().rev().map(|&child| (depth + 1, child)));
+            }
+        }
+        result
+    }
+}
+
+/// 親のインデックスから子のインデックスへの対応表を作る（自身を親とする場合は除く）
+fn child_map(parents: &[Option<usize>]) -> HashMap<usize, Vec<usize>> {
+    let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (index, parent) in parents.iter().enumerate() {
+        if let Some(parent) = *parent
+            && parent != index
+        {
+            children.entry(parent).or_default().push(index);
+        }
+    }
+    children
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FixedClock;
+    use chrono::NaiveDate;
+
+    const CONTENT: &str = "\
+Release id:r
+Write docs id:d parent:r
+Proofread parent:d
+Tag version parent:r
+Unrelated
+Lost child parent:missing";
+
+    #[test]
+    fn test_parent_tag() {
+        let mut todo = Todo::new("Subtask");
+        todo.set_parent_id("42");
+        assert_eq!(todo.parent_id(), Some("42"));
+        assert_eq!(todo.to_string(), "Subtask parent:42");
+    }
+
+    #[test]
+    fn test_children_and_ancestors() {
+        let list = TodoList::from_string(CONTENT).unwrap();
+        assert_eq!(list.children(0), [1, 3]);
+        assert_eq!(list.children(2), Vec::<usize>::new());
+        assert_eq!(list.ancestors(2), [1, 0]);
+        assert_eq!(list.descendants(0), [1, 2, 3]);
+        assert_eq!(list.parent_of(5), None);
+    }
+
+    #[test]
+    fn test_completion_percentage() {
+        let mut list = TodoList::from_string(CONTENT).unwrap();
+        assert_eq!(list.completion_percentage(0).unwrap(), 0.0);
+
+        list.complete(2).unwrap();
+        assert_eq!(list.completion_percentage(1).unwrap(), 100.0);
+        assert!((list.completion_percentage(0).unwrap() - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(list.completion_percentage(2).unwrap(), 100.0);
+        assert!(list.completion_percentage(10).is_err());
+    }
+
+    #[test]
+    fn test_complete_cascade() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let mut list = TodoList::from_string(CONTENT)
+            .unwrap()
+            .with_clock(FixedClock::new(date));
+        list.complete(2).unwrap();
+
+        assert_eq!(list.complete_cascade(0).unwrap(), [0, 1, 3]);
+        assert!(list.iter().take(4).all(|todo| todo.completed));
+        assert!(!list.get(4).unwrap().completed);
+        assert_eq!(list.get(3).unwrap().completion_date, Some(date));
+    }
+
+    #[test]
+    fn test_complete_cascade_is_one_undo_step() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let mut list = TodoList::from_string(CONTENT)
+            .unwrap()
+            .with_clock(FixedClock::new(date))
+            .with_history(10);
+
+        assert_eq!(list.complete_cascade(0).unwrap(), [0, 1, 2, 3]);
+        list.undo().unwrap();
+        assert!(list.iter().all(|todo| !todo.completed));
+        assert!(!list.can_undo());
+
+        list.redo().unwrap();
+        assert!(list.iter().take(4).all(|todo| todo.completed));
+    }
+
+    #[test]
+    fn test_orphans() {
+        let list = TodoList::from_string(CONTENT).unwrap();
+        assert_eq!(list.orphans(), [5]);
+
+        // 重複した ID は依存関係と同じくエラーとして扱い、親を特定しない
+        let list = TodoList::from_string("A id:1\nB id:1\nC parent:1").unwrap();
+        assert!(matches!(
+            list.check_dependencies(),
+            Err(TodoError::DuplicateTaskId(id)) if id == "1"
+        ));
+        assert_eq!(list.parent_of(2), None);
+        assert_eq!(list.orphans(), [2]);
+    }
+
+    #[test]
+    fn test_tree() {
+        let list = TodoList::from_string(CONTENT).unwrap();
+        let nodes: Vec<(usize, &str)> = list
+            .tree()
+            .map(|node| (node.depth, node.todo.description.as_str()))
+            .collect();
+        assert_eq!(
+            nodes,
+            [
+                (0, "Release"),
+                (1, "Write docs"),
+                (2, "Proofread"),
+                (1, "Tag version"),
+                (0, "Unrelated"),
+                (0, "Lost child"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tree_with_cycle() {
+        let list = TodoList::from_string("A id:a parent:b\nB id:b parent:a").unwrap();
+        let nodes: Vec<(usize, usize)> = list.tree().map(|node| (node.depth, node.index)).collect();
+        assert_eq!(nodes, [(0, 0), (1, 1)]);
+        assert_eq!(list.ancestors(0), [1]);
+    }
+}
@@ -0,0 +1,586 @@
+use crate::id::TaskId;
+use crate::save::{SaveOptions, write_atomic};
+use crate::{Result, Todo, TodoError, TodoList};
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// ジャーナルファイルの 1 行目
+const JOURNAL_HEADER: &str = "# todo-rs journal v1";
+
+/// 元に戻すことができる TodoList への操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Operation {
+    /// `index` の位置にタスクを追加した
+    Add {
+        index: usize,
+        id: Option<TaskId>,
+        todo: Todo,
+    },
+    /// `index` の位置のタスクを削除した
+    Remove {
+        index: usize,
+        id: Option<TaskId>,
+        todo: Todo,
+    },
+    /// `index` の位置のタスクを変更した
+    Update {
+        index: usize,
+        before: Todo,
+        after: Todo,
+    },
+    /// タスクを並べ替えた（新しい `i` 番目は元の `order[i]` 番目）
+    Reorder { order: Vec<usize> },
+    /// 複数の操作をまとめて行った
+    Batch(Vec<Operation>),
+}
+
+impl Operation {
+    /// 操作を行った後のタスク数を求め、インデックスが範囲内か確認する
+    fn check_apply(&self, len: usize) -> Result<usize> {
+        match self {
+            Operation::Add { index, .. } if *index <= len => Ok(len + 1),
+            Operation::Remove { index, .. } | Operation::Update { index, .. } if *index < len => {
+                Ok(len - usize::from(matches!(self, Operation::Remove { .. })))
+            }
+            Operation::Reorder { order } if is_permutation(order, len) => Ok(len),
+            Operation::Batch(operations) => operations
+                .iter()
+                .try_fold(len, |len, operation| operation.check_apply(len)),
+            Operation::Add { index, .. }
+            | Operation::Remove { index, .. }
+            | Operation::Update { index, .. } => Err(TodoError::IndexOutOfBounds(*index)),
+            Operation::Reorder { order } => Err(TodoError::IndexOutOfBounds(order.len())),
+        }
+    }
+
+    /// 操作を取り消した後のタスク数を求め、インデックスが範囲内か確認する
+    fn check_revert(&self, len: usize) -> Result<usize> {
+        match self {
+            Operation::Add { index, .. } | Operation::Update { index, .. } if *index < len => {
+                Ok(len - usize::from(matches!(self, Operation::Add { .. })))
+            }
+            Operation::Remove { index, .. } if *index <= len => Ok(len + 1),
+            Operation::Reorder { order } if is_permutation(order, len) => Ok(len),
+            Operation::Batch(operations) => operations
+                .iter()
+                .rev()
+                .try_fold(len, |len, operation| operation.check_revert(len)),
+            Operation::Add { index, .. }
+            | Operation::Remove { index, .. }
+            | Operation::Update { index, .. } => Err(TodoError::IndexOutOfBounds(*index)),
+            Operation::Reorder { order } => Err(TodoError::IndexOutOfBounds(order.len())),
+        }
+    }
+
+    /// 操作をもう一度行う
+    fn apply(&self, list: &mut TodoList) {
+        match self {
+            Operation::Add { index, id, todo } => {
+                list.insert_entry(*index, todo.clone(), *id);
+            }
+            Operation::Remove { index, .. } => {
+                list.remove_entry(*index);
+            }
+            Operation::Update { index, after, .. } => {
+                list.replace_entry(*index, after.clone());
+            }
+            Operation::Reorder { order } => list.permute_entries(order),
+            Operation::Batch(operations) => {
+                for operation in operations {
+                    operation.apply(list);
+                }
+            }
+        }
+    }
+
+    /// 操作を取り消す
+    fn revert(&self, list: &mut TodoList) {
+        match self {
+            Operation::Add { index, .. } => {
+                list.remove_entry(*index);
+            }
+            Operation::Remove { index, id, todo } => {
+                list.insert_entry(*index, todo.clone(), *id);
+            }
+            Operation::Update { index, before, .. } => {
+                list.replace_entry(*index, before.clone());
+            }
+            Operation::Reorder { order } => list.permute_entries(&inverse(order)),
+            Operation::Batch(operations) => {
+                for operation in operations.iter().rev() {
+                    operation.revert(list);
+                }
+            }
+        }
+    }
+
+    /// ジャーナル形式で書き出す
+    fn write_to(&self, out: &mut Vec<String>) {
+        match self {
+            Operation::Add { index, todo, .. } => {
+                out.push(format!("add {}", index));
+                out.push(todo.to_string());
+            }
+            Operation::Remove { index, todo, .. } => {
+                out.push(format!("remove {}", index));
+                out.push(todo.to_string());
+            }
+            Operation::Update {
+                index,
+                before,
+                after,
+            } => {
+                out.push(format!("update {}", index));
+                out.push(before.to_string());
+                out.push(after.to_string());
+            }
+            Operation::Reorder { order } => {
+                let order: Vec<String> = order.iter().map(usize::to_string).collect();
+                out.push(
+                    format!("reorder {}", order.join(" "))
+                        .trim_end()
+                        .to_string(),
+                );
+            }
+            Operation::Batch(operations) => {
+                out.push(format!("batch {}", operations.len()));
+                for operation in operations {
+                    operation.write_to(out);
+                }
+            }
+        }
+    }
+
+    /// ジャーナル形式から読み込む
+    fn read_from<'a, I>(lines: &mut I) -> Result<Self>
+    where
+        I: Iterator<Item = (usize, &'a str)>,
+    {
+        let (line_num, header) = lines
+            .next()
+            .ok_or_else(|| journal_error(0, "操作が途中で終わっています"))?;
+        let (name, args) = header.split_once(' ').unwrap_or((header, ""));
+
+        let number = |s: &str| {
+            s.parse::<usize>()
+                .map_err(|_| journal_error(line_num, "数値ではありません"))
+        };
+        let mut todo = || -> Result<Todo> {
+            let (n, line) = lines
+                .next()
+                .ok_or_else(|| journal_error(line_num, "タスクの行がありません"))?;
+            line.parse()
+                .map_err(|e: TodoError| journal_error(n, &e.to_string()))
+        };
+
+        match name {
+            // ID はメモリ上でのみ有効なため、読み込み時は新しく割り当てる
+            "add" => Ok(Operation::Add {
+                index: number(args)?,
+                id: None,
+                todo: todo()?,
+            }),
+            "remove" => Ok(Operation::Remove {
+                index: number(args)?,
+                id: None,
+                todo: todo()?,
+            }),
+            "update" => Ok(Operation::Update {
+                index: number(args)?,
+                before: todo()?,
+                after: todo()?,
+            }),
+            "reorder" => Ok(Operation::Reorder {
+                order: args.split_whitespace().map(number).collect::<Result<_>>()?,
+            }),
+            "batch" => {
+                let count = number(args)?;
+                let operations = (0..count)
+                    .map(|_| Operation::read_from(lines))
+                    .collect::<Result<_>>()?;
+                Ok(Operation::Batch(operations))
+            }
+            _ => Err(journal_error(line_num, "不明な操作です")),
+        }
+    }
+}
+
+/// `order` が `0..len` の並べ替えかチェック
+fn is_permutation(order: &[usize], len: usize) -> bool {
+    let mut seen = vec![false; len];
+    order.len() == len
+        && order
+            .iter()
+            .all(|&i| i < len && !std::mem::replace(&mut seen[i], true))
+}
+
+/// 並べ替えの逆を求める
+fn inverse(order: &[usize]) -> Vec<usize> {
+    let mut inverse = vec![0; order.len()];
+    for (new, &old) in order.iter().enumerate() {
+        inverse[old] = new;
+    }
+    inverse
+}
+
+fn journal_error(line: usize, message: &str) -> TodoError {
+    TodoError::ParseError(format!("ジャーナルの{}行目: {}", line, message))
+}
+
+/// 操作履歴
+#[derive(Debug, Clone, Default)]
+pub(crate) struct History {
+    /// 保持する操作の最大数 (0 の場合は記録しない)
+    depth: usize,
+    undo: VecDeque<Operation>,
+    redo: Vec<Operation>,
+    /// `get_mut` で貸し出したタスクの、貸し出し前の状態
+    pending: Option<(usize, Todo)>,
+}
+
+impl History {
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.depth > 0
+    }
+
+    /// 新しい操作を記録する（やり直しの履歴は破棄される）
+    pub(crate) fn record(&mut self, operation: Operation) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.redo.clear();
+        self.push_undo(operation);
+    }
+
+    fn push_undo(&mut self, operation: Operation) {
+        self.undo.push_back(operation);
+        while self.undo.len() > self.depth {
+            self.undo.pop_front();
+        }
+    }
+
+    /// 変更前の状態を記録しておく
+    pub(crate) fn set_pending(&mut self, index: usize, before: Todo) {
+        if self.is_enabled() {
+            self.pending = Some((index, before));
+        }
+    }
+}
+
+fn pending_operation(index: usize, before: Todo, todos: &[Todo]) -> Option<Operation> {
+    let after = todos.get(index)?;
+    (*after != before).then(|| Operation::Update {
+        index,
+        before,
+        after: after.clone(),
+    })
+}
+
+impl TodoList {
+    /// 操作履歴を有効にし、保持する操作の最大数を設定
+    ///
+    /// 0 を指定すると履歴は記録されない（デフォルト）。
+    /// `add`, `remove`, `complete`, 並べ替え、`get_mut` による変更が記録される。
+    /// `all_mut` や `iter_mut` による変更は記録されない。
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.flush_pending();
+        let history = self.history_mut();
+        history.depth = depth;
+        while history.undo.len() > depth {
+            history.undo.pop_front();
+        }
+        history.redo.truncate(depth);
+    }
+
+    /// 操作履歴を有効にする
+    pub fn with_history(mut self, depth: usize) -> Self {
+        self.set_history_depth(depth);
+        self
+    }
+
+    /// 直前の操作を取り消す
+    ///
+    /// 取り消す操作がない場合は `false` を返す。
+    pub fn undo(&mut self) -> Result<bool> {
+        self.flush_pending();
+        let Some(operation) = self.history_mut().undo.pop_back() else {
+            return Ok(false);
+        };
+
+        if let Err(e) = operation.check_revert(self.len()) {
+            self.history_mut().undo.push_back(operation);
+            return Err(e);
+        }
+        operation.revert(self);
+        self.history_mut().redo.push(operation);
+
+        Ok(true)
+    }
+
+    /// 取り消した操作をやり直す
+    ///
+    /// やり直す操作がない場合は `false` を返す。
+    pub fn redo(&mut self) -> Result<bool> {
+        self.flush_pending();
+        let Some(operation) = self.history_mut().redo.pop() else {
+            return Ok(false);
+        };
+
+        if let Err(e) = operation.check_apply(self.len()) {
+            self.history_mut().redo.push(operation);
+            return Err(e);
+        }
+        operation.apply(self);
+        self.history_mut().push_undo(operation);
+
+        Ok(true)
+    }
+
+    /// 取り消せる操作があるかチェック
+    pub fn can_undo(&self) -> bool {
+        !self.history().undo.is_empty() || self.pending_operation().is_some()
+    }
+
+    /// やり直せる操作があるかチェック
+    pub fn can_redo(&self) -> bool {
+        !self.history().redo.is_empty()
+    }
+
+    /// 操作履歴を消去
+    pub fn clear_history(&mut self) {
+        let history = self.history_mut();
+        history.undo.clear();
+        history.redo.clear();
+        history.pending = None;
+    }
+
+    /// 貸し出し中のタスクの変更を履歴に記録する
+    pub(crate) fn flush_pending(&mut self) {
+        self.sync_index();
+        let Some((index, before)) = self.history_mut().pending.take() else {
+            return;
+        };
+        if let Some(operation) = pending_operation(index, before, self.all()) {
+            self.history_mut().record(operation);
+        }
+    }
+
+    /// 貸し出し中のタスクの変更（まだ記録されていないもの）
+    fn pending_operation(&self) -> Option<Operation> {
+        let (index, before) = self.history().pending.clone()?;
+        pending_operation(index, before, self.all())
+    }
+
+    /// todo ファイルに対応するジャーナルファイルのパス (`todo.txt.journal`)
+    pub fn journal_path<P: AsRef<Path>>(todo_path: P) -> PathBuf {
+        let mut name = todo_path.as_ref().as_os_str().to_owned();
+        name.push(".journal");
+        PathBuf::from(name)
+    }
+
+    /// 操作履歴を todo ファイルの隣のジャーナルファイルに保存
+    ///
+    /// 再起動後に [`TodoList::load_journal`] で復元できるよう、
+    /// todo ファイルと同じタイミングで保存する必要がある。
+    pub fn save_journal<P: AsRef<Path>>(&self, todo_path: P) -> Result<()> {
+        let history = self.history();
+        let mut lines = vec![JOURNAL_HEADER.to_string(), "undo".to_string()];
+        for operation in history.undo.iter().chain(&self.pending_operation()) {
+            operation.write_to(&mut lines);
+        }
+        lines.push("redo".to_string());
+        for operation in &history.redo {
+            operation.write_to(&mut lines);
+        }
+
+        let mut content = lines.join("\n");
+        content.push('\n');
+        write_atomic(
+            &Self::journal_path(todo_path),
+            content.as_bytes(),
+            &SaveOptions::new(),
+        )
+    }
+
+    /// todo ファイルの隣のジャーナルファイルから操作履歴を読み込む
+    ///
+    /// ジャーナルファイルがない場合は `false` を返す。
+    /// 読み込まれる操作の数は [`TodoList::set_history_depth`] で設定した最大数までになる。
+    pub fn load_journal<P: AsRef<Path>>(&mut self, todo_path: P) -> Result<bool> {
+        let content = match fs::read_to_string(Self::journal_path(todo_path)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut lines = content
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line))
+            .peekable();
+        if lines.next().map(|(_, line)| line) != Some(JOURNAL_HEADER) {
+            return Err(journal_error(1, "ジャーナルファイルではありません"));
+        }
+        if lines.next().map(|(_, line)| line) != Some("undo") {
+            return Err(journal_error(2, "undo がありません"));
+        }
+
+        let mut undo = Vec::new();
+        while lines.peek().is_some_and(|&(_, line)| line != "redo") {
+            undo.push(Operation::read_from(&mut lines)?);
+        }
+        if lines.next().is_none() {
+            return Err(journal_error(content.lines().count(), "redo がありません"));
+        }
+        let mut redo = Vec::new();
+        while lines.peek().is_some() {
+            redo.push(Operation::read_from(&mut lines)?);
+        }
+
+        self.clear_history();
+        let history = self.history_mut();
+        for operation in undo {
+            history.push_undo(operation);
+        }
+        let skip = redo.len().saturating_sub(history.depth);
+        history.redo = redo.into_iter().skip(skip).collect();
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Priority;
+
+    fn descriptions(list: &TodoList) -> Vec<&str> {
+        list.iter().map(|todo| todo.description.as_str()).collect()
+    }
+
+    #[test]
+    fn test_undo_redo_add_remove() {
+        let mut list = TodoList::new().with_history(10);
+        list.add(Todo::new("Task 1"));
+        list.add(Todo::new("Task 2"));
+        list.remove(0).unwrap();
+        assert_eq!(descriptions(&list), ["Task 2"]);
+
+        assert!(list.undo().unwrap());
+        assert_eq!(descriptions(&list), ["Task 1", "Task 2"]);
+        assert!(list.undo().unwrap());
+        assert_eq!(descriptions(&list), ["Task 1"]);
+
+        assert!(list.redo().unwrap());
+        assert!(list.redo().unwrap());
+        assert_eq!(descriptions(&list), ["Task 2"]);
+        assert!(!list.redo().unwrap());
+    }
+
+    #[test]
+    fn test_undo_get_mut_and_sort() {
+        let mut list = TodoList::from_string("Task B\nTask A")
+            .unwrap()
+            .with_history(10);
+        list.get_mut(0).unwrap().priority = Priority::new('A');
+        list.sort_by_description();
+        assert_eq!(list.to_string(), "Task A\n(A) Task B");
+
+        assert!(list.undo().unwrap());
+        assert_eq!(list.to_string(), "(A) Task B\nTask A");
+        assert!(list.undo().unwrap());
+        assert_eq!(list.to_string(), "Task B\nTask A");
+        assert!(!list.undo().unwrap());
+    }
+
+    #[test]
+    fn test_undo_complete_recurring() {
+        let mut list = TodoList::from_string("Water plants rec:1w")
+            .unwrap()
+            .with_history(10);
+        list.complete(0).unwrap();
+        assert_eq!(list.len(), 2);
+
+        assert!(list.undo().unwrap());
+        assert_eq!(list.len(), 1);
+        assert!(!list.get(0).unwrap().completed);
+    }
+
+    #[test]
+    fn test_history_depth() {
+        let mut list = TodoList::new().with_history(2);
+        for i in 0..5 {
+            list.add(Todo::new(format!("Task {}", i)));
+        }
+        assert!(list.undo().unwrap());
+        assert!(list.undo().unwrap());
+        assert!(!list.undo().unwrap());
+        assert_eq!(list.len(), 3);
+    }
+
+    #[test]
+    fn test_history_disabled_by_default() {
+        let mut list = TodoList::new();
+        list.add(Todo::new("Task 1"));
+        assert!(!list.can_undo());
+        assert!(!list.undo().unwrap());
+    }
+
+    #[test]
+    fn test_new_operation_clears_redo() {
+        let mut list = TodoList::new().with_history(10);
+        list.add(Todo::new("Task 1"));
+        list.undo().unwrap();
+        assert!(list.can_redo());
+        list.add(Todo::new("Task 2"));
+        assert!(!list.can_redo());
+    }
+
+    #[test]
+    fn test_journal_roundtrip() {
+        let dir = crate::test_util::temp_dir("journal");
+        let path = dir.join("todo.txt");
+
+        let mut list = TodoList::from_string("Task C\nTask A\nundo")
+            .unwrap()
+            .with_history(10);
+        list.remove(2).unwrap();
+        list.sort_by_description();
+        list.get_mut(0).unwrap().add_project("Work");
+        list.add(Todo::new("Task B"));
+        list.undo().unwrap();
+        list.save_to_file(&path).unwrap();
+        list.save_journal(&path).unwrap();
+
+        let mut restored = TodoList::from_file(&path).unwrap().with_history(10);
+        assert!(restored.load_journal(&path).unwrap());
+        assert!(restored.redo().unwrap());
+        assert_eq!(restored.to_string(), "Task A +Work\nTask C\nTask B");
+        while restored.undo().unwrap() {}
+        assert_eq!(restored.to_string(), "Task C\nTask A\nundo");
+
+        assert!(
+            !TodoList::new()
+                .load_journal(dir.join("missing.txt"))
+                .unwrap()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_undo_out_of_range_is_kept() {
+        let mut list = TodoList::new().with_history(10);
+        list.add(Todo::new("Task 1"));
+        list.clear_history();
+        list.history_mut().record(Operation::Remove {
+            index: 3,
+            id: None,
+            todo: Todo::new("Task 1"),
+        });
+
+        assert!(list.undo().is_err());
+        assert!(list.can_undo());
+    }
+}
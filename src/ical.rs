@@ -0,0 +1,590 @@
+use crate::todo::UID_KEY;
+use crate::{Priority, Result, Todo, TodoError, TodoList};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+/// このクレートが生成した UID の接尾辞
+const UID_SUFFIX: &str = "@todo-rs";
+
+/// todo.txt のタグを保持する独自プロパティ
+const TAG_PROPERTY: &str = "X-TODOTXT-TAG";
+
+/// PRIORITY で表せない優先度を保持する独自プロパティ
+const PRIORITY_PROPERTY: &str = "X-TODOTXT-PRIORITY";
+
+/// 1 行の最大オクテット数（改行を除く）
+const MAX_LINE_OCTETS: usize = 75;
+
+impl Todo {
+    /// iCalendar の UID を取得
+    ///
+    /// `uid:` タグがあればその値、`id:` タグがあればそれを元にした値を返し、
+    /// どちらもない場合は `None`。
+    /// [`TodoList::to_ics`] は `None` のタスクに作成日と説明文から UID を作る。
+    /// その UID は並べ替えでは変わらないが説明文を編集すると変わるため、
+    /// 編集後も同じ UID にしたい場合は [`TodoList::assign_persistent_ids`] で `id:` を付けておく。
+    pub fn ical_uid(&self) -> Option<String> {
+        if let Some(uid) = self.get_tag(UID_KEY) {
+            return Some(uid.clone());
+        }
+        self.persistent_id()
+            .map(|id| format!("id-{}{}", id, UID_SUFFIX))
+    }
+
+    /// `VTODO` コンポーネントとして書き出す
+    fn write_vtodo(&self, out: &mut String, uid: &str, stamp: NaiveDate) {
+        let date_time = |date: NaiveDate| date.format("%Y%m%dT000000Z").to_string();
+        let date = |date: NaiveDate| date.format("%Y%m%d").to_string();
+
+        push_line(out, "BEGIN:VTODO");
+        push_line(out, &format!("UID:{}", escape_text(uid)));
+        push_line(out, &format!("DTSTAMP:{}", date_time(stamp)));
+        push_line(out, &format!("SUMMARY:{}", escape_text(&self.description)));
+
+        let status = if self.completed {
+            "COMPLETED"
+        } else {
+            "NEEDS-ACTION"
+        };
+        push_line(out, &format!("STATUS:{}", status));
+
+        if let Some(priority) = self.priority {
+            let (level, overflow) = priority_level(priority);
+            push_line(out, &format!("PRIORITY:{}", level));
+            if overflow {
+                push_line(
+                    out,
+                    &format!("{}:{}", PRIORITY_PROPERTY, priority.as_char()),
+                );
+            }
+        }
+        if let Some(created) = self.creation_date {
+            push_line(out, &format!("CREATED:{}", date_time(created)));
+        }
+        if let Some(completed) = self.completion_date {
+            push_line(out, &format!("COMPLETED:{}", date_time(completed)));
+        }
+
+        let (due, threshold) = self.export_dates();
+        if let Some(threshold) = threshold {
+            push_line(out, &format!("DTSTART;VALUE=DATE:{}", date(threshold)));
+        }
+        if let Some(due) = due {
+            push_line(out, &format!("DUE;VALUE=DATE:{}", date(due)));
+        }
+
+        let categories: Vec<String> = self
+            .projects
+            .iter()
+            .map(|p| format!("+{}", p))
+            .chain(self.contexts.iter().map(|c| format!("@{}", c)))
+            .map(|c| escape_text(&c))
+            .collect();
+        if !categories.is_empty() {
+            push_line(out, &format!("CATEGORIES:{}", categories.join(",")));
+        }
+
+        for (key, value) in self.export_tags() {
+            if key == UID_KEY {
+                continue;
+            }
+            push_line(
+                out,
+                &format!(
+                    "{}:{}",
+                    TAG_PROPERTY,
+                    escape_text(&format!("{}:{}", key, value))
+                ),
+            );
+        }
+
+        push_line(out, "END:VTODO");
+    }
+}
+
+impl TodoList {
+    /// iCalendar (RFC 5545) 形式で出力
+    ///
+    /// 各タスクは `VTODO` になる。`due:` は `DUE`、`t:` は `DTSTART`、作成日は `CREATED`、
+    /// 完了日は `COMPLETED`、優先度 A〜I は `PRIORITY` の 1〜9、
+    /// プロジェクトとコンテキストは `+` / `@` を付けて `CATEGORIES` に対応する。
+    /// その他のタグは独自プロパティに保存し、[`TodoList::from_ics`] で元に戻せる。
+    /// `DTSTAMP` にはリストの時計の今日を使う。
+    /// `UID` は [`Todo::ical_uid`] を使い、`uid:` も `id:` もないタスクには作成日と説明文から作る。
+    /// 作成日と説明文が同じタスクには、2 つ目以降に出現順の番号を付けて重複を避ける。
+    pub fn to_ics(&self) -> String {
+        let mut out = String::new();
+        push_line(&mut out, "BEGIN:VCALENDAR");
+        push_line(&mut out, "VERSION:2.0");
+        push_line(&mut out, "PRODID:-//seichiki//todo-rs//JA");
+        let stamp = self.today();
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for todo in self.iter() {
+            let uid = todo.ical_uid().unwrap_or_else(|| {
+                let uid = content_uid(todo);
+                let count = seen.entry(uid.clone()).or_default();
+                *count += 1;
+                match *count {
+                    1 => uid,
+                    n => uid.replacen(UID_SUFFIX, &format!("-{}{}", n, UID_SUFFIX), 1),
+                }
+            });
+            todo.write_vtodo(&mut out, &uid, stamp);
+        }
+        push_line(&mut out, "END:VCALENDAR");
+        out
+    }
+
+    /// iCalendar 形式から `VTODO` を読み込む
+    ///
+    /// `VTODO` 以外のコンポーネントと、対応していないプロパティは無視する。
+    /// `CATEGORIES` のうち `@` で始まるものはコンテキスト、それ以外はプロジェクトになる。
+    /// このクレートが生成したものではない `UID` は `uid:` タグとして保持する。
+    /// todo.txt と同様、`CATEGORIES` があれば `SUMMARY` は空でもよい。
+    /// 読み込めない場合は行番号を持つ [`TodoError::Import`] を返す。
+    pub fn from_ics(content: &str) -> Result<TodoList> {
+        let mut list = TodoList::new();
+        let mut current: Option<(usize, Todo)> = None;
+        let mut depth = 0;
+
+        for (line_num, line) in unfold(content) {
+            let error = |message: String| ical_error(line_num, message);
+            let (name, value) = split_property(&line)
+                .ok_or_else(|| error(format!("プロパティとして読めません: {}", line)))?;
+
+            match (name.as_str(), value) {
+                ("BEGIN", "VTODO") if current.is_none() => {
+                    current = Some((line_num, Todo::new("")));
+                    depth = 0;
+                }
+                ("END", "VTODO") if depth == 0 => {
+                    let Some((start, mut todo)) = current.take() else {
+                        return Err(error("対応する BEGIN:VTODO がありません".to_string()));
+                    };
+                    // todo.txt と同様、プロジェクトかコンテキストがあれば説明文は空でもよい
+                    if todo.description.is_empty()
+                        && todo.projects.is_empty()
+                        && todo.contexts.is_empty()
+                    {
+                        return Err(ical_error(start, "SUMMARY がありません".to_string()));
+                    }
+                    todo.normalize_completion();
+                    list.add(todo);
+                }
+                // VTODO 内の VALARM などは読み飛ばす
+                ("BEGIN", _) if current.is_some() => depth += 1,
+                ("END", _) if current.is_some() => depth -= 1,
+                _ => {
+                    if let Some((_, todo)) = current.as_mut()
+                        && depth == 0
+                    {
+                        apply_property(todo, &name, value).map_err(error)?;
+                    }
+                }
+            }
+        }
+
+        if let Some((start, _)) = current {
+            return Err(ical_error(start, "END:VTODO がありません".to_string()));
+        }
+        Ok(list)
+    }
+}
+
+/// VTODO のプロパティを `Todo` に反映する
+fn apply_property(todo: &mut Todo, name: &str, value: &str) -> std::result::Result<(), String> {
+    let date = || parse_ical_date(value).ok_or_else(|| format!("日付として読めません: {}", value));
+
+    match name {
+        "SUMMARY" => {
+            todo.description = unescape_text(value)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+        }
+        "UID" => {
+            let uid = unescape_text(value);
+            if !uid.ends_with(UID_SUFFIX) && !uid.is_empty() && !uid.contains(char::is_whitespace) {
+                todo.add_tag(UID_KEY, uid);
+            }
+        }
+        "STATUS" => todo.completed = value.eq_ignore_ascii_case("COMPLETED"),
+        "PRIORITY" => {
+            let level: u8 = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("優先度として読めません: {}", value))?;
+            // 独自プロパティで細かい優先度が指定されていれば、そちらを優先する
+            if todo.priority.is_none_or(|p| p.as_char() <= 'I') {
+                todo.priority = match level {
+                    0 => None,
+                    1..=9 => Priority::new((b'A' + level - 1) as char),
+                    _ => return Err(format!("優先度は 0〜9 である必要があります: {}", value)),
+                };
+            }
+        }
+        PRIORITY_PROPERTY => {
+            todo.priority = Some(
+                value
+                    .chars()
+                    .next()
+                    .and_then(Priority::new)
+                    .ok_or_else(|| format!("優先度として読めません: {}", value))?,
+            );
+        }
+        "CREATED" => todo.creation_date = Some(date()?),
+        "COMPLETED" => {
+            todo.completed = true;
+            todo.completion_date = Some(date()?);
+        }
+        "DUE" => todo.set_due_date(date()?),
+        "DTSTART" => todo.set_threshold_date(date()?),
+        "CATEGORIES" => {
+            for category in split_list(value) {
+                let category = category.split_whitespace().collect::<Vec<_>>().join("-");
+                if let Some(context) = category.strip_prefix('@') {
+                    if !context.is_empty() && !todo.has_context(context) {
+                        todo.add_context(context);
+                    }
+                } else {
+                    let project = category.strip_prefix('+').unwrap_or(&category);
+                    if !project.is_empty() && !todo.has_project(project) {
+                        todo.add_project(project);
+                    }
+                }
+            }
+        }
+        TAG_PROPERTY => {
+            let tag = unescape_text(value);
+            match tag.split_once(':') {
+                Some((key, value)) if !key.is_empty() && !value.is_empty() => {
+                    todo.add_tag(key, value)
+                }
+                _ => return Err(format!("タグとして読めません: {}", tag)),
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// `uid:` も `id:` もないタスクの UID（作成日と説明文の FNV-1a ハッシュ）
+fn content_uid(todo: &Todo) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let date = todo
+        .creation_date
+        .map(|d| d.to_string())
+        .unwrap_or_default();
+    let bytes = date.bytes().chain([0]).chain(todo.description.bytes());
+    for byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}{}", hash, UID_SUFFIX)
+}
+
+/// 優先度を PRIORITY の値に変換（I より低い優先度は 9 にまとめ、そのことを返す）
+fn priority_level(priority: Priority) -> (u8, bool) {
+    let level = priority.as_char() as u8 - b'A' + 1;
+    (level.min(9), level > 9)
+}
+
+fn ical_error(line: usize, message: String) -> TodoError {
+    TodoError::Import {
+        format: "iCalendar",
+        line,
+        message,
+    }
+}
+
+/// `YYYYMMDD` または `YYYYMMDDTHHMMSS[Z]` の日付部分を読む
+fn parse_ical_date(value: &str) -> Option<NaiveDate> {
+    let date = value.trim().get(..8)?;
+    NaiveDate::parse_from_str(date, "%Y%m%d").ok()
+}
+
+/// 折り返された行を元に戻し、(開始行番号, 行) の組にする
+fn unfold(content: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some((_, last))) => last.push_str(rest),
+            _ if line.is_empty() => {}
+            _ => lines.push((i + 1, line.to_string())),
+        }
+    }
+    lines
+}
+
+/// `NAME;PARAM=...:VALUE` を (大文字の名前, 値) に分ける（パラメーターは使わない）
+fn split_property(line: &str) -> Option<(String, &str)> {
+    // パラメーターの値は引用符で囲まれている場合があり、その中の `:` は区切りではない
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(i),
+        _ => None,
+    })?;
+
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let name = head.split_once(';').map_or(head, |(name, _)| name);
+    if name.is_empty() {
+        return None;
+    }
+    Some((name.to_ascii_uppercase(), value))
+}
+
+/// TEXT 型の値をエスケープする
+fn escape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// TEXT 型の値のエスケープを戻す
+fn unescape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// エスケープされていない `,` で区切られた TEXT のリストを分ける
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ',' => {
+                items.push(unescape_text(&value[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(unescape_text(&value[start..]));
+    items
+}
+
+/// 75 オクテットで折り返して 1 行を書き出す（改行は CRLF）
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FixedClock;
+    use crate::test_util::assert_same_todos;
+
+    fn list(content: &str) -> TodoList {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        TodoList::from_string(content)
+            .unwrap()
+            .with_clock(FixedClock::new(today))
+    }
+
+    #[test]
+    fn test_export_vtodo() {
+        let ics =
+            list("(A) 2024-01-15 Call mom, later +Family @phone due:2024-03-05 id:7").to_ics();
+        let expected = "\
+BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//seichiki//todo-rs//JA\r
+BEGIN:VTODO\r
+UID:id-7@todo-rs\r
+DTSTAMP:20240301T000000Z\r
+SUMMARY:Call mom\\, later\r
+STATUS:NEEDS-ACTION\r
+PRIORITY:1\r
+CREATED:20240115T000000Z\r
+DUE;VALUE=DATE:20240305\r
+CATEGORIES:+Family,@phone\r
+X-TODOTXT-TAG:id:7\r
+END:VTODO\r
+END:VCALENDAR\r
+";
+        assert_eq!(ics, expected);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let content = "\
+(B) 2024-01-15 Write report; draft +Work @office due:2024-03-05 t:2024-03-01 est:2
+x 2024-02-01 2024-01-20 Pay rent +Home
+(M) Someday task rec:1w due:soon
+Plain task uid:abc-123@example.com
++Work @home";
+        let original = list(content);
+        let back = TodoList::from_ics(&original.to_ics()).unwrap();
+
+        assert_same_todos(&back, &original);
+    }
+
+    #[test]
+    fn test_ical_uid() {
+        let todo: Todo = "2024-01-15 Call mom".parse().unwrap();
+        assert_eq!(todo.ical_uid(), None);
+
+        let todo: Todo = "Call mom id:5".parse().unwrap();
+        assert_eq!(todo.ical_uid().as_deref(), Some("id-5@todo-rs"));
+
+        let todo: Todo = "Call mom id:5 uid:xyz".parse().unwrap();
+        assert_eq!(todo.ical_uid().as_deref(), Some("xyz"));
+    }
+
+    #[test]
+    fn test_fallback_uids() {
+        let mut list = list("Buy milk\nBuy milk");
+        let uids = |list: &TodoList| -> Vec<String> {
+            list.to_ics()
+                .lines()
+                .filter_map(|line| line.strip_prefix("UID:"))
+                .map(str::to_string)
+                .collect()
+        };
+
+        let fallback = uids(&list);
+        assert_eq!(fallback.len(), 2);
+        assert_eq!(
+            fallback[1],
+            fallback[0].replace(UID_SUFFIX, &format!("-2{}", UID_SUFFIX))
+        );
+        assert_eq!(uids(&list), fallback);
+
+        // 並べ替えても UID は変わらない
+        let mut sorted = self::list("Buy milk\nCall mom");
+        let before = uids(&sorted);
+        sorted.sort_by(|a, b| b.description.cmp(&a.description));
+        let mut after = uids(&sorted);
+        after.reverse();
+        assert_eq!(after, before);
+
+        // id: を付ければ内容を編集しても UID は変わらない
+        list.assign_persistent_ids();
+        list.get_mut(0).unwrap().description = "Buy oat milk".to_string();
+        assert_eq!(uids(&list), ["id-1@todo-rs", "id-2@todo-rs"]);
+    }
+
+    #[test]
+    fn test_line_folding() {
+        let description = "とても長い説明文".repeat(6);
+        let ics = list(&description).to_ics();
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert!(ics.contains("\r\n "));
+
+        let back = TodoList::from_ics(&ics).unwrap();
+        assert_eq!(back.get(0).unwrap().description, description);
+    }
+
+    #[test]
+    fn test_import_foreign_calendar() {
+        let ics = "\
+BEGIN:VCALENDAR\r
+BEGIN:VEVENT\r
+SUMMARY:Not a task\r
+END:VEVENT\r
+BEGIN:VTODO\r
+UID:12345@calendar.example\r
+SUMMARY:Buy\\, then\r
+  return milk\r
+PRIORITY:5\r
+DUE;TZID=\"Asia/Tokyo\":20240310T090000\r
+CATEGORIES:Errands,Home Stuff\r
+CATEGORIES:@store\r
+STATUS:COMPLETED\r
+COMPLETED:20240309T120000Z\r
+BEGIN:VALARM\r
+SUMMARY:Ignored\r
+END:VALARM\r
+END:VTODO\r
+END:VCALENDAR\r
+";
+        let list = TodoList::from_ics(ics).unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.get(0).unwrap().priority, Priority::new('E'));
+        assert_eq!(
+            list.get(0).unwrap().to_string(),
+            "x 2024-03-09 Buy, then return milk +Errands +Home-Stuff @store due:2024-03-10 uid:12345@calendar.example"
+        );
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let error = |ics: &str| match TodoList::from_ics(ics) {
+            Err(TodoError::Import {
+                format: "iCalendar",
+                line,
+                message,
+            }) => (line, message),
+            other => panic!("unexpected result: {other:?}"),
+        };
+
+        assert_eq!(error("BEGIN:VTODO\nSUMMARY:A\n").0, 1);
+        assert!(
+            error("BEGIN:VTODO\nUID:x\nEND:VTODO\n")
+                .1
+                .contains("SUMMARY")
+        );
+        assert_eq!(
+            error("BEGIN:VTODO\nSUMMARY:A\nDUE:tomorrow\nEND:VTODO").0,
+            3
+        );
+        let (line, message) = error("BEGIN:VTODO\nSUMMARY:A\nPRIORITY:high\nEND:VTODO");
+        assert_eq!(line, 3);
+        assert!(message.contains("優先度"));
+        assert!(
+            error("BEGIN:VTODO\ngarbage\nEND:VTODO")
+                .1
+                .contains("garbage")
+        );
+
+        let message = TodoList::from_ics("BEGIN:VTODO\n").unwrap_err().to_string();
+        assert!(message.starts_with("iCalendar の1行目"), "{message}");
+    }
+
+    #[test]
+    fn test_escape_text() {
+        let text = "a\\b;c,d\ne";
+        assert_eq!(escape_text(text), "a\\\\b\\;c\\,d\\ne");
+        assert_eq!(unescape_text(&escape_text(text)), text);
+        assert_eq!(split_list("a\\,b,c"), ["a,b", "c"]);
+    }
+}
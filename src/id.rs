@@ -0,0 +1,196 @@
+use crate::{Result, Todo, TodoError, TodoList};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// 永続的な ID を表すタグのキー
+pub(crate) const ID_KEY: &str = "id";
+
+/// リスト内のタスクを識別する ID
+///
+/// 並べ替えや他のタスクの削除によってインデックスが変わっても、同じタスクを指し続ける。
+/// ID はメモリ上でのみ有効で、ファイルには保存されない。
+/// ファイルに保存する ID が必要な場合は [`TodoList::assign_persistent_ids`] を使用する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaskId(u64);
+
+impl TaskId {
+    pub(crate) fn new(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+impl fmt::Display for TaskId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+impl TodoList {
+    /// ID でタスクを取得
+    pub fn get_by_id(&self, id: TaskId) -> Option<&Todo> {
+        self.index_of(id).and_then(|index| self.get(index))
+    }
+
+    /// ID でタスクを可変参照で取得
+    pub fn get_mut_by_id(&mut self, id: TaskId) -> Option<&mut Todo> {
+        let index = self.index_of(id)?;
+        self.get_mut(index)
+    }
+
+    /// ID でタスクを削除
+    pub fn remove_by_id(&mut self, id: TaskId) -> Result<Todo> {
+        let index = self.index_of(id).ok_or(TodoError::TaskNotFound(id))?;
+        self.remove(index)
+    }
+
+    /// ID でタスクを更新
+    ///
+    /// 操作履歴が有効な場合、変更は 1 つの操作として記録される。
+    pub fn update_by_id<F>(&mut self, id: TaskId, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Todo),
+    {
+        let todo = self.get_mut_by_id(id).ok_or(TodoError::TaskNotFound(id))?;
+        f(todo);
+        self.flush_pending();
+        Ok(())
+    }
+
+    /// 永続的な ID (`id:` タグ) でタスクを検索
+    pub fn find_by_persistent_id(&self, value: &str) -> Option<TaskId> {
+        self.iter_with_ids()
+            .find(|(_, todo)| todo.persistent_id() == Some(value))
+            .map(|(id, _)| id)
+    }
+
+    /// `id:` タグのないタスクに、重複しない連番の ID を割り当てる
+    ///
+    /// 既存の数値 ID の最大値の次から割り当てる。戻り値は割り当てたタスクの数。
+    pub fn assign_persistent_ids(&mut self) -> usize {
+        let used: HashSet<String> = self
+            .iter()
+            .filter_map(|todo| todo.persistent_id().map(str::to_string))
+            .collect();
+        let mut next = used
+            .iter()
+            .filter_map(|value| value.parse::<u64>().ok())
+            .max()
+            .map_or(1, |max| max.saturating_add(1));
+
+        let targets: Vec<TaskId> = self
+            .iter_with_ids()
+            .filter(|(_, todo)| todo.persistent_id().is_none())
+            .map(|(id, _)| id)
+            .collect();
+
+        for &id in &targets {
+            while used.contains(&next.to_string()) {
+                next += 1;
+            }
+            let value = next.to_string();
+            next += 1;
+            // update_by_id は対象が存在する限り失敗しない
+            let _ = self.update_by_id(id, |todo| todo.add_tag(ID_KEY, value));
+        }
+
+        targets.len()
+    }
+
+    /// 永続的な ID (`id:`) からインデックスへの対応表を作る
+    ///
+    /// 複数のタスクが持つ ID はどれを指すか決められないため対応表に含めず、
+    /// そのうちリストで最初に重複が見つかった ID を合わせて返す。
+    pub(crate) fn persistent_id_map(&self) -> (HashMap<&str, usize>, Option<&str>) {
+        let mut by_id = HashMap::new();
+        let mut duplicates = HashSet::new();
+        let mut first_duplicate = None;
+        for (index, todo) in self.iter().enumerate() {
+            if let Some(id) = todo.persistent_id()
+                && by_id.insert(id, index).is_some()
+            {
+                duplicates.insert(id);
+                first_duplicate.get_or_insert(id);
+            }
+        }
+        by_id.retain(|id, _| !duplicates.contains(id));
+        (by_id, first_duplicate)
+    }
+
+    /// どのタスクにも使われていない新しい数値の永続的な ID
+    pub(crate) fn fresh_persistent_id(&self) -> String {
+        self.iter()
+            .filter_map(|todo| todo.persistent_id()?.parse::<u64>().ok())
+            .max()
+            .map_or(1, |max| max.saturating_add(1))
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_survives_sort_and_remove() {
+        let mut list = TodoList::new();
+        let c = list.add(Todo::new("Task C"));
+        let a = list.add(Todo::new("Task A"));
+        let b = list.add(Todo::new("Task B"));
+
+        list.sort_by_description();
+        assert_eq!(list.index_of(a), Some(0));
+        assert_eq!(list.get_by_id(c).unwrap().description, "Task C");
+
+        list.remove_by_id(a).unwrap();
+        assert_eq!(list.index_of(b), Some(0));
+        assert!(list.get_by_id(a).is_none());
+        assert!(matches!(
+            list.remove_by_id(a),
+            Err(TodoError::TaskNotFound(id)) if id == a
+        ));
+    }
+
+    #[test]
+    fn test_update_by_id() {
+        let mut list = TodoList::from_string("Task 1\nTask 2")
+            .unwrap()
+            .with_history(10);
+        let id = list.id_at(1).unwrap();
+
+        list.update_by_id(id, |todo| todo.add_project("Work"))
+            .unwrap();
+        assert!(list.get(1).unwrap().has_project("Work"));
+
+        list.undo().unwrap();
+        assert!(!list.get(1).unwrap().has_project("Work"));
+    }
+
+    #[test]
+    fn test_undo_restores_id() {
+        let mut list = TodoList::new().with_history(10);
+        let id = list.add(Todo::new("Task 1"));
+        list.remove_by_id(id).unwrap();
+        list.undo().unwrap();
+        assert_eq!(list.index_of(id), Some(0));
+    }
+
+    #[test]
+    fn test_assign_persistent_ids() {
+        let mut list =
+            TodoList::from_string("Task 1 id:7\nTask 2\nTask 3 id:abc\nTask 4 id:9\nTask 5")
+                .unwrap();
+        assert_eq!(list.assign_persistent_ids(), 2);
+        assert_eq!(list.get(1).unwrap().persistent_id(), Some("10"));
+        assert_eq!(list.get(4).unwrap().persistent_id(), Some("11"));
+        assert_eq!(list.assign_persistent_ids(), 0);
+
+        let id = list.find_by_persistent_id("abc").unwrap();
+        assert_eq!(list.index_of(id), Some(2));
+        assert!(list.find_by_persistent_id("12").is_none());
+    }
+
+    #[test]
+    fn test_display_task_id() {
+        assert_eq!(TaskId::new(3).to_string(), "#3");
+    }
+}
@@ -0,0 +1,510 @@
+use crate::{Todo, TodoList};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// キーに一致するタスクの位置
+type Postings = BTreeSet<usize>;
+
+/// プロジェクト・コンテキスト・タグからタスクの位置を引く索引
+///
+/// 位置は `TodoList` 内のインデックスで、挿入・削除のたびにずらす。
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TaskIndex {
+    projects: HashMap<String, Postings>,
+    contexts: HashMap<String, Postings>,
+    tags: HashMap<String, HashMap<String, Postings>>,
+
+    /// 索引に登録しているタスクの数
+    len: usize,
+
+    /// `get_mut` で貸し出し中のタスクの位置と、貸し出し前のキー
+    lent: Option<(usize, Keys)>,
+
+    /// `all_mut` などで、どのタスクが変わったか分からない状態
+    stale: bool,
+}
+
+/// 1 つのタスクが索引に登録されるキー
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Keys {
+    projects: Vec<String>,
+    contexts: Vec<String>,
+    tags: Vec<(String, String)>,
+}
+
+impl Keys {
+    fn of(todo: &Todo) -> Self {
+        let unique = |values: &[String]| {
+            let mut values = values.to_vec();
+            values.sort();
+            values.dedup();
+            values
+        };
+        Self {
+            projects: unique(&todo.projects),
+            contexts: unique(&todo.contexts),
+            tags: todo
+                .tags
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        }
+    }
+
+    fn names(&self, field: Field<'_>) -> Vec<&str> {
+        match field {
+            Field::Project => self.projects.iter().map(String::as_str).collect(),
+            Field::Context => self.contexts.iter().map(String::as_str).collect(),
+            Field::TagKey => self.tags.iter().map(|(k, _)| k.as_str()).collect(),
+            Field::TagValue(key) => self
+                .tags
+                .iter()
+                .filter(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+                .collect(),
+        }
+    }
+}
+
+/// 一覧を作る対象
+#[derive(Debug, Clone, Copy)]
+enum Field<'a> {
+    Project,
+    Context,
+    TagKey,
+    TagValue(&'a str),
+}
+
+impl TaskIndex {
+    pub(crate) fn build(todos: &[Todo]) -> Self {
+        let mut index = Self::default();
+        for (pos, todo) in todos.iter().enumerate() {
+            index.add(pos, &Keys::of(todo));
+        }
+        index.len = todos.len();
+        index
+    }
+
+    /// 貸し出し中・不明な変更を索引に反映する
+    pub(crate) fn sync(&mut self, todos: &[Todo]) {
+        if self.stale {
+            *self = Self::build(todos);
+        } else if let Some((pos, before)) = self.lent.take() {
+            self.remove(pos, &before);
+            self.add(pos, &Keys::of(&todos[pos]));
+        }
+    }
+
+    pub(crate) fn inserted(&mut self, pos: usize, todo: &Todo) {
+        // 末尾への追加ではずらす必要がない
+        if pos < self.len {
+            self.shift(pos, |p| p + 1);
+        }
+        self.add(pos, &Keys::of(todo));
+        self.len += 1;
+    }
+
+    pub(crate) fn removed(&mut self, pos: usize, todo: &Todo) {
+        self.remove(pos, &Keys::of(todo));
+        self.len -= 1;
+        if pos < self.len {
+            self.shift(pos + 1, |p| p - 1);
+        }
+    }
+
+    pub(crate) fn replaced(&mut self, pos: usize, before: &Todo, after: &Todo) {
+        self.remove(pos, &Keys::of(before));
+        self.add(pos, &Keys::of(after));
+    }
+
+    pub(crate) fn lend(&mut self, pos: usize, todo: &Todo) {
+        self.lent = Some((pos, Keys::of(todo)));
+    }
+
+    pub(crate) fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    fn add(&mut self, pos: usize, keys: &Keys) {
+        for project in &keys.projects {
+            self.projects
+                .entry(project.clone())
+                .or_default()
+                .insert(pos);
+        }
+        for context in &keys.contexts {
+            self.contexts
+                .entry(context.clone())
+                .or_default()
+                .insert(pos);
+        }
+        for (key, value) in &keys.tags {
+            self.tags
+                .entry(key.clone())
+                .or_default()
+                .entry(value.clone())
+                .or_default()
+                .insert(pos);
+        }
+    }
+
+    fn remove(&mut self, pos: usize, keys: &Keys) {
+        fn remove_from(map: &mut HashMap<String, Postings>, key: &str, pos: usize) {
+            if let Some(postings) = map.get_mut(key) {
+                postings.remove(&pos);
+                if postings.is_empty() {
+                    map.remove(key);
+                }
+            }
+        }
+
+        for project in &keys.projects {
+            remove_from(&mut self.projects, project, pos);
+        }
+        for context in &keys.contexts {
+            remove_from(&mut self.contexts, context, pos);
+        }
+        for (key, value) in &keys.tags {
+            if let Some(values) = self.tags.get_mut(key) {
+                remove_from(values, value, pos);
+                if values.is_empty() {
+                    self.tags.remove(key);
+                }
+            }
+        }
+    }
+
+    /// `from` 以降の位置を `f` で付け替える
+    fn shift(&mut self, from: usize, f: impl Fn(usize) -> usize) {
+        let all = self
+            .projects
+            .values_mut()
+            .chain(self.contexts.values_mut())
+            .chain(self.tags.values_mut().flat_map(HashMap::values_mut));
+        for postings in all {
+            let tail = postings.split_off(&from);
+            postings.extend(tail.into_iter().map(&f));
+        }
+    }
+
+    fn postings(&self, field: Field<'_>, name: &str) -> Option<&Postings> {
+        match field {
+            Field::Project => self.projects.get(name),
+            Field::Context => self.contexts.get(name),
+            Field::TagKey => None,
+            Field::TagValue(key) => self.tags.get(key)?.get(name),
+        }
+    }
+
+    fn counts(&self, field: Field<'_>) -> BTreeMap<&str, usize> {
+        fn count(map: &HashMap<String, Postings>) -> BTreeMap<&str, usize> {
+            map.iter().map(|(k, v)| (k.as_str(), v.len())).collect()
+        }
+
+        match field {
+            Field::Project => count(&self.projects),
+            Field::Context => count(&self.contexts),
+            Field::TagKey => self
+                .tags
+                .iter()
+                .map(|(key, values)| (key.as_str(), values.values().map(BTreeSet::len).sum()))
+                .collect(),
+            Field::TagValue(key) => self.tags.get(key).map(count).unwrap_or_default(),
+        }
+    }
+}
+
+impl TodoList {
+    /// プロジェクト・コンテキスト・タグの索引を有効にする
+    ///
+    /// 有効にすると [`with_project`](TodoList::with_project) などの検索が
+    /// 全件の走査ではなく索引を使うようになる。索引はタスクの追加・削除・変更に合わせて更新される。
+    pub fn enable_index(&mut self) {
+        self.flush_pending();
+        let index = TaskIndex::build(self.all());
+        *self.task_index_mut() = Some(index);
+    }
+
+    /// 索引を有効にする（ビルダー形式）
+    pub fn with_index(mut self) -> Self {
+        self.enable_index();
+        self
+    }
+
+    /// 索引を無効にする
+    pub fn disable_index(&mut self) {
+        *self.task_index_mut() = None;
+    }
+
+    /// 索引が有効かチェック
+    pub fn is_indexed(&self) -> bool {
+        self.task_index().is_some()
+    }
+
+    /// 特定のタグを持つタスクを取得
+    pub fn with_tag(&self, key: &str) -> Vec<&Todo> {
+        match self.usable_index() {
+            Some(index) => {
+                let positions: BTreeSet<usize> = index
+                    .tags
+                    .get(key)
+                    .into_iter()
+                    .flat_map(HashMap::values)
+                    .flatten()
+                    .copied()
+                    .collect();
+                self.collect_positions(index, &positions, |todo| todo.has_tag(key))
+            }
+            None => self.filter(|todo| todo.has_tag(key)),
+        }
+    }
+
+    /// タグの値が一致するタスクを取得
+    pub fn with_tag_value(&self, key: &str, value: &str) -> Vec<&Todo> {
+        self.lookup(Field::TagValue(key), value, |todo| {
+            todo.get_tag(key).is_some_and(|v| v == value)
+        })
+    }
+
+    /// プロジェクトの一覧をタスク数とともに名前順で取得
+    pub fn projects(&self) -> Vec<(String, usize)> {
+        self.counts(Field::Project)
+    }
+
+    /// コンテキストの一覧をタスク数とともに名前順で取得
+    pub fn contexts(&self) -> Vec<(String, usize)> {
+        self.counts(Field::Context)
+    }
+
+    /// タグのキーの一覧をタスク数とともに名前順で取得
+    pub fn tag_keys(&self) -> Vec<(String, usize)> {
+        self.counts(Field::TagKey)
+    }
+
+    /// タグの値の一覧をタスク数とともに名前順で取得
+    pub fn tag_values(&self, key: &str) -> Vec<(String, usize)> {
+        self.counts(Field::TagValue(key))
+    }
+
+    /// 特定のプロジェクトのタスクを索引から取得
+    pub(crate) fn lookup_project(&self, project: &str) -> Vec<&Todo> {
+        self.lookup(Field::Project, project, |todo| todo.has_project(project))
+    }
+
+    /// 特定のコンテキストのタスクを索引から取得
+    pub(crate) fn lookup_context(&self, context: &str) -> Vec<&Todo> {
+        self.lookup(Field::Context, context, |todo| todo.has_context(context))
+    }
+
+    /// 走査せずに使える状態の索引
+    fn usable_index(&self) -> Option<&TaskIndex> {
+        self.task_index().as_ref().filter(|index| !index.stale)
+    }
+
+    fn lookup(&self, field: Field<'_>, name: &str, matches: impl Fn(&Todo) -> bool) -> Vec<&Todo> {
+        match self.usable_index() {
+            Some(index) => {
+                let empty = Postings::new();
+                let positions = index.postings(field, name).unwrap_or(&empty);
+                self.collect_positions(index, positions, matches)
+            }
+            None => self.filter(matches),
+        }
+    }
+
+    /// 索引の位置からタスクを集める（貸し出し中のタスクは現在の内容で判定する）
+    fn collect_positions(
+        &self,
+        index: &TaskIndex,
+        positions: &Postings,
+        matches: impl Fn(&Todo) -> bool,
+    ) -> Vec<&Todo> {
+        let todos = self.all();
+        let mut positions = positions.clone();
+        if let Some((pos, _)) = &index.lent {
+            positions.remove(pos);
+            if matches(&todos[*pos]) {
+                positions.insert(*pos);
+            }
+        }
+        positions.into_iter().map(|pos| &todos[pos]).collect()
+    }
+
+    fn counts(&self, field: Field<'_>) -> Vec<(String, usize)> {
+        let mut counts: BTreeMap<&str, usize>;
+        let fresh: Vec<Keys>;
+
+        match self.usable_index() {
+            Some(index) => {
+                counts = index.counts(field);
+                if let Some((pos, before)) = &index.lent {
+                    for name in before.names(field) {
+                        if let Some(count) = counts.get_mut(name) {
+                            *count -= 1;
+                        }
+                    }
+                    fresh = vec![Keys::of(&self.all()[*pos])];
+                } else {
+                    fresh = Vec::new();
+                }
+            }
+            None => {
+                counts = BTreeMap::new();
+                fresh = self.iter().map(Keys::of).collect();
+            }
+        }
+
+        for keys in &fresh {
+            for name in keys.names(field) {
+                *counts.entry(name).or_default() += 1;
+            }
+        }
+
+        counts
+            .into_iter()
+            .filter(|&(_, count)| count > 0)
+            .map(|(name, count)| (name.to_string(), count))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "\
+Write docs +Docs @home size:s
+Review +Work @office size:l
+Deploy +Work +Ops @office
+Call mom @phone size:s";
+
+    fn descriptions(todos: Vec<&Todo>) -> Vec<&str> {
+        todos
+            .into_iter()
+            .map(|todo| todo.description.as_str())
+            .collect()
+    }
+
+    /// 索引を使った結果が全件走査と一致するかチェック
+    fn assert_consistent(list: &TodoList) {
+        let mut plain = list.clone();
+        plain.disable_index();
+        assert_eq!(list.projects(), plain.projects());
+        assert_eq!(list.contexts(), plain.contexts());
+        assert_eq!(list.tag_keys(), plain.tag_keys());
+        for (project, _) in plain.projects() {
+            assert_eq!(list.with_project(&project), plain.with_project(&project));
+        }
+        for (context, _) in plain.contexts() {
+            assert_eq!(list.with_context(&context), plain.with_context(&context));
+        }
+        for (key, _) in plain.tag_keys() {
+            assert_eq!(list.with_tag(&key), plain.with_tag(&key));
+            for (value, _) in plain.tag_values(&key) {
+                assert_eq!(
+                    list.with_tag_value(&key, &value),
+                    plain.with_tag_value(&key, &value)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_listings() {
+        let list = TodoList::from_string(CONTENT).unwrap();
+        let expected = |pairs: &[(&str, usize)]| -> Vec<(String, usize)> {
+            pairs.iter().map(|&(n, c)| (n.to_string(), c)).collect()
+        };
+
+        assert_eq!(
+            list.projects(),
+            expected(&[("Docs", 1), ("Ops", 1), ("Work", 2)])
+        );
+        assert_eq!(
+            list.contexts(),
+            expected(&[("home", 1), ("office", 2), ("phone", 1)])
+        );
+        assert_eq!(list.tag_keys(), expected(&[("size", 3)]));
+        assert_eq!(list.tag_values("size"), expected(&[("l", 1), ("s", 2)]));
+
+        let indexed = list.with_index();
+        assert!(indexed.is_indexed());
+        assert_consistent(&indexed);
+    }
+
+    #[test]
+    fn test_indexed_lookups() {
+        let list = TodoList::from_string(CONTENT).unwrap().with_index();
+        assert_eq!(
+            descriptions(list.with_project("Work")),
+            ["Review", "Deploy"]
+        );
+        assert_eq!(descriptions(list.with_context("phone")), ["Call mom"]);
+        assert_eq!(
+            descriptions(list.with_tag_value("size", "s")),
+            ["Write docs", "Call mom"]
+        );
+        assert_eq!(descriptions(list.with_tag("size")).len(), 3);
+        assert!(list.with_project("Nothing").is_empty());
+    }
+
+    #[test]
+    fn test_index_follows_add_and_remove() {
+        let mut list = TodoList::from_string(CONTENT).unwrap().with_index();
+
+        list.add("Plan +Work @home".parse().unwrap());
+        list.remove(0).unwrap();
+        assert_eq!(
+            descriptions(list.with_project("Work")),
+            ["Review", "Deploy", "Plan"]
+        );
+        assert_eq!(descriptions(list.with_context("home")), ["Plan"]);
+        assert_consistent(&list);
+
+        list.drain_where(|todo| todo.has_project("Ops"));
+        list.sort_by_description();
+        assert_consistent(&list);
+    }
+
+    #[test]
+    fn test_index_follows_mutation() {
+        let mut list = TodoList::from_string(CONTENT).unwrap().with_index();
+
+        // 貸し出し中の変更も次の操作を待たずに反映される
+        list.get_mut(1).unwrap().add_project("Ops");
+        assert_eq!(descriptions(list.with_project("Ops")), ["Review", "Deploy"]);
+        assert_consistent(&list);
+
+        list.complete(0).unwrap();
+        assert_consistent(&list);
+
+        for todo in list.iter_mut() {
+            todo.remove_tag("size");
+        }
+        assert!(list.with_tag("size").is_empty());
+        assert_consistent(&list);
+
+        list.add(Todo::new("Flush"));
+        assert_consistent(&list);
+    }
+
+    #[test]
+    fn test_index_follows_undo() {
+        let mut list = TodoList::from_string(CONTENT)
+            .unwrap()
+            .with_history(10)
+            .with_index();
+
+        list.remove(1).unwrap();
+        list.update_by_id(list.id_at(0).unwrap(), |todo| todo.add_project("Work"))
+            .unwrap();
+        assert_consistent(&list);
+
+        while list.undo().unwrap() {
+            assert_consistent(&list);
+        }
+        assert_eq!(
+            descriptions(list.with_project("Work")),
+            ["Review", "Deploy"]
+        );
+    }
+}
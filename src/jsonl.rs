@@ -0,0 +1,217 @@
+use crate::parser::parse_line;
+use crate::{ParseDiagnostic, ParseErrorKind, ParseReport, Todo, TodoList};
+use serde::{Deserialize, Serialize};
+
+/// JSON Lines に書き出すレコード
+#[derive(Serialize)]
+struct OutRecord<'a> {
+    #[serde(flatten)]
+    todo: &'a Todo,
+    /// todo.txt での行番号 (1 始まり)
+    line: usize,
+    /// パース元の行
+    raw: Option<&'a str>,
+}
+
+/// JSON Lines から読み込むレコード（`line` は読み込み時には使わない）
+#[derive(Deserialize)]
+struct InRecord {
+    #[serde(flatten)]
+    todo: Todo,
+    #[serde(default)]
+    raw: Option<String>,
+}
+
+impl TodoList {
+    /// JSON Lines 形式（1 行に 1 タスクの JSON オブジェクト）で出力
+    ///
+    /// 各オブジェクトは `Todo` のシリアライズ結果のフィールドに加えて、
+    /// todo.txt での行番号 `line` と、パース元の行 `raw`（ない場合は `null`）を持つ。
+    /// パースできずに保持している行は出力されない。
+    ///
+    /// ```
+    /// use todo_rs::TodoList;
+    ///
+    /// let list = TodoList::from_string("(A) Call mom @phone").unwrap();
+    /// let jsonl = list.to_jsonl();
+    /// assert!(jsonl.contains("\"raw\":\"(A) Call mom @phone\""));
+    /// ```
+    pub fn to_jsonl(&self) -> String {
+        let mut out = String::new();
+        for (todo, line) in self.iter().zip(self.line_numbers()) {
+            let record = OutRecord {
+                todo,
+                line,
+                raw: todo.original_line(),
+            };
+            // 文字列・真偽値・数値・null と、キーが文字列のマップだけなので失敗しない
+            // (test_to_jsonl_every_field_type を参照)
+            out.push_str(&serde_json::to_string(&record).expect("JSON へのシリアライズに失敗"));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// JSON Lines 形式から読み込む
+    ///
+    /// 読めないレコードは読み飛ばし、入力の行番号とともに診断情報として返す。空行は無視する。
+    /// `raw` がフィールドの内容と一致する場合は、書き出し時にその行のトークン順を保つ。
+    pub fn from_jsonl(content: &str) -> ParseReport {
+        let mut report = ParseReport::default();
+        let mut offset = 0;
+
+        for (line_num, segment) in content.split_inclusive('\n').enumerate() {
+            let start = offset;
+            offset += segment.len();
+
+            let line = segment.trim_end_matches('\n').trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<InRecord>(line) {
+                Ok(record) => {
+                    report.list.add(restore_raw(record));
+                }
+                Err(error) => report.diagnostics.push(ParseDiagnostic {
+                    line: line_num + 1,
+                    column: error.column().max(1),
+                    span: start..start + line.len(),
+                    raw: line.to_string(),
+                    kind: ParseErrorKind::InvalidRecord(error.to_string()),
+                }),
+            }
+        }
+
+        report
+    }
+}
+
+/// `raw` をパースした結果がフィールドと一致する場合だけ、元の行として使う
+fn restore_raw(record: InRecord) -> Todo {
+    let todo = record.todo;
+    match record.raw.map(|raw| parse_line(&raw)) {
+        Some(Ok(parsed)) if parsed == todo => parsed,
+        _ => todo,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParsePolicy;
+    use serde_json::Value;
+
+    #[test]
+    fn test_to_jsonl() {
+        let list = TodoList::from_string("(A) Call mom @phone\nx Done +Work est:2").unwrap();
+        let jsonl = list.to_jsonl();
+        let records: Vec<Value> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["priority"], "A");
+        assert_eq!(records[0]["line"], 1);
+        assert_eq!(records[0]["raw"], "(A) Call mom @phone");
+        assert_eq!(records[1]["completed"], true);
+        assert_eq!(records[1]["tags"]["est"], "2");
+        assert!(jsonl.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_line_numbers_count_preserved_lines() {
+        let content = "First\nx\nSecond\nx 2024-01-01\nThird";
+        let list = TodoList::from_string_with(content, ParsePolicy::Preserve)
+            .unwrap()
+            .into_list();
+        let lines: Vec<u64> = list
+            .to_jsonl()
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<Value>(line).unwrap()["line"]
+                    .as_u64()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(lines, [1, 3, 5]);
+
+        let mut list = list;
+        list.add(Todo::new("Fourth"));
+        let last = list.to_jsonl().lines().last().unwrap().to_string();
+        assert!(last.contains("\"raw\":null"));
+    }
+
+    #[test]
+    fn test_round_trip_keeps_token_order() {
+        let content = "(B) 2024-01-15 Write docs est:2 +Work due:2024-02-01 @office\nBuy milk";
+        let list = TodoList::from_string(content).unwrap();
+        let report = TodoList::from_jsonl(&list.to_jsonl());
+        assert!(report.is_clean());
+        assert_eq!(report.list.to_string(), content);
+    }
+
+    #[test]
+    fn test_edited_record_ignores_stale_raw() {
+        let jsonl = r#"{"description":"New text","raw":"Old text","tags":{"est":"3"}}"#;
+        let list = TodoList::from_jsonl(jsonl).into_list();
+        assert_eq!(list.to_string(), "New text est:3");
+    }
+
+    #[test]
+    fn test_to_jsonl_every_field_type() {
+        let mut built = Todo::new("Quote \" back\\slash 日本語 \u{1f600}")
+            .with_priority(crate::Priority::new('Z').unwrap())
+            .with_creation_date(chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+        built.complete_on(chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap());
+        built.add_project("Work");
+        built.add_context("desk");
+        built.add_tag("url", "https://example.com/?q=\"x\"");
+
+        let mut list = TodoList::from_string("(A) 2024-01-01 Parsed +P @c key:value").unwrap();
+        list.add(built.clone());
+        list.add(Todo::new("Plain"));
+
+        let report = TodoList::from_jsonl(&list.to_jsonl());
+        assert!(report.is_clean());
+        assert_eq!(report.list.len(), 3);
+        assert_eq!(report.list.get(1).unwrap(), &built);
+        for (restored, original) in report.list.iter().zip(list.iter()) {
+            assert_eq!(restored, original);
+        }
+    }
+
+    #[test]
+    fn test_bad_records() {
+        let jsonl = "\
+{\"description\":\"Good\"}
+
+not json
+{\"priority\":\"A\"}
+{\"description\":\"Bad date\",\"creation_date\":\"2024-13-01\"}
+{\"description\":\"Also good\"}
+{\"description\":\"a\\nb\"}
+{\"description\":\"Spaced\",\"projects\":[\"two words\"]}
+{\"description\":\"\"}
+{\"description\":\"Empty tag\",\"tags\":{\"due\":\"\"}}
+{\"description\":\"Bad key\",\"tags\":{\"a:b\":\"c\"}}
+";
+        let report = TodoList::from_jsonl(jsonl);
+        assert_eq!(report.list.len(), 2);
+
+        let lines: Vec<usize> = report.diagnostics.iter().map(|d| d.line).collect();
+        assert_eq!(lines, [3, 4, 5, 7, 8, 9, 10, 11]);
+        assert!(
+            report.diagnostics[3..]
+                .iter()
+                .all(|d| matches!(d.kind, ParseErrorKind::InvalidRecord(_)))
+        );
+        assert_eq!(report.diagnostics[0].raw, "not json");
+        assert!(matches!(
+            report.diagnostics[1].kind,
+            ParseErrorKind::InvalidRecord(ref message) if message.contains("description")
+        ));
+        assert_eq!(&jsonl[report.diagnostics[0].span.clone()], "not json");
+    }
+}
@@ -9,15 +9,60 @@
 //! - CRUD 操作（作成、読み込み、更新、削除）
 //! - フィルタリング（優先度、プロジェクト、コンテキスト）
 //! - ソート（優先度、日付、説明）
+//! - 期限日 (`due:`)・着手可能日 (`t:`)・繰り返し (`rec:`) のサポート
+//! - `serde` フィーチャーによる `Serialize` / `Deserialize` の実装
+//! - `json` フィーチャーによる JSON Lines の入出力
 
+mod archive;
+mod clock;
+mod csv;
+mod dependency;
+mod diagnostic;
 mod error;
+mod hierarchy;
+mod history;
+mod ical;
+mod id;
+mod index;
+#[cfg(feature = "json")]
+mod jsonl;
 mod list;
+mod lock;
+mod markdown;
+mod merge;
+mod org;
 mod parser;
 mod priority;
+mod query;
+mod recurrence;
+mod report;
+mod save;
+mod search;
+#[cfg(feature = "serde")]
+mod serialize;
+mod sort;
+#[cfg(test)]
+mod test_util;
 mod todo;
 
+pub use clock::{Clock, FixedClock, OffsetClock, SystemClock};
+pub use csv::{CsvField, CsvMapping};
+pub use diagnostic::{ParseDiagnostic, ParseErrorKind, ParsePolicy, ParseReport};
 pub use error::{Result, TodoError};
+pub use hierarchy::TreeNode;
+pub use id::TaskId;
 pub use list::TodoList;
+pub use lock::{LockMethod, LockOptions, LockedTodoList};
+pub use markdown::{MarkdownOptions, NestedItems};
+pub use merge::MergeConflict;
 pub use parser::parse_todo;
 pub use priority::Priority;
+pub use query::{CompareOp, DateValue, Query, QueryValue};
+pub use recurrence::{Recurrence, RecurrenceUnit};
+pub use report::{GroupBy, GroupStats, Report};
+pub use save::{LineEnding, SaveOptions};
+pub use search::{SearchHit, SearchOptions};
+#[cfg(feature = "serde")]
+pub use serialize::line;
+pub use sort::{SortField, SortKey, SortSpec, TagKind};
 pub use todo::Todo;
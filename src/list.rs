@@ -1,59 +1,256 @@
-use crate::{Result, Todo, TodoError};
+use crate::clock::{Clock, SystemClock};
+use crate::history::{History, Operation};
+use crate::id::TaskId;
+use crate::index::TaskIndex;
+use crate::parser::parse_line;
+use crate::save::LineEnding;
+use crate::{ParseDiagnostic, ParsePolicy, ParseReport, Result, Todo, TodoError};
+use chrono::{Days, NaiveDate};
+use std::collections::HashMap;
 use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// 複数の Todo タスクを管理するリスト
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct TodoList {
     todos: Vec<Todo>,
+
+    /// 各タスクの ID (`todos` と同じ順序)
+    ids: Vec<TaskId>,
+
+    /// 次に発行する ID
+    next_id: u64,
+
+    /// 空行と、パースできずにそのまま保持している行
+    preserved: Vec<PreservedLine>,
+
+    /// 完了日や繰り返しの計算に使うクロック
+    clock: Arc<dyn Clock>,
+
+    /// 保存時の改行コード
+    line_ending: LineEnding,
+
+    /// 保存時に最終行の後に改行を付けるかどうか
+    final_newline: bool,
+
+    /// 読み込み時（または最後の保存時）のファイルの内容
+    baseline: Option<Baseline>,
+
+    /// 元に戻すための操作履歴
+    history: History,
+
+    /// プロジェクト・コンテキスト・タグの索引（有効な場合のみ）
+    task_index: Option<TaskIndex>,
+}
+
+/// 外部での変更を検出するために記録しておくファイルの内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Baseline {
+    pub(crate) path: PathBuf,
+    /// ファイルの内容そのもの（変更の検出に使う）
+    pub(crate) content: String,
+    /// 記録時のリストを書き出した内容（3-way マージの基準に使う）
+    ///
+    /// 読み飛ばした行や行頭・行末の空白は含まれないため、ファイルの内容とは異なる場合がある。
+    pub(crate) merge_base: String,
+}
+
+impl Default for TodoList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 空行と、パースできずにそのまま保持している行
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PreservedLine {
+    /// この行より前にあるタスクの数
+    position: usize,
+    text: String,
 }
 
 impl TodoList {
     /// 新しい空の TodoList を作成
     pub fn new() -> Self {
-        Self { todos: Vec::new() }
+        Self {
+            todos: Vec::new(),
+            ids: Vec::new(),
+            next_id: 0,
+            preserved: Vec::new(),
+            clock: Arc::new(SystemClock),
+            line_ending: LineEnding::default(),
+            final_newline: false,
+            baseline: None,
+            history: History::default(),
+            task_index: None,
+        }
+    }
+
+    /// クロックを設定
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.set_clock(clock);
+        self
+    }
+
+    /// クロックを差し替え
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Arc::new(clock);
+    }
+
+    /// クロックを取得
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    /// クロックによる今日の日付を取得
+    ///
+    /// [`TodoList::overdue`] などの日付を受け取るクエリに渡すことができる。
+    pub fn today(&self) -> NaiveDate {
+        self.clock.today()
+    }
+
+    /// 保存時の改行コードを取得
+    ///
+    /// 文字列やファイルから読み込んだ場合は、元の改行コードになる。
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    /// 保存時の改行コードを設定
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
+    /// 保存時に最終行の後に改行を付けるかどうかを取得
+    pub fn has_final_newline(&self) -> bool {
+        self.final_newline
+    }
+
+    /// 保存時に最終行の後に改行を付けるかどうかを設定
+    pub fn set_final_newline(&mut self, final_newline: bool) {
+        self.final_newline = final_newline;
     }
 
     /// ファイルから TodoList を読み込み
+    ///
+    /// パースできない行は読み飛ばされる。
+    /// 診断情報が必要な場合は [`TodoList::from_file_with`] を使用する。
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_file_with(path, ParsePolicy::Lenient).map(ParseReport::into_list)
+    }
+
+    /// 指定したポリシーでファイルから TodoList を読み込み
+    ///
+    /// 読み込んだ内容は保存時に外部での変更を検出するために記録される。
+    pub fn from_file_with<P: AsRef<Path>>(path: P, policy: ParsePolicy) -> Result<ParseReport> {
+        let path = path.as_ref();
         let content = fs::read_to_string(path)?;
-        Self::from_string(&content)
+        let mut report = Self::from_string_with(&content, policy)?;
+        report.list.set_baseline(path, content);
+        Ok(report)
+    }
+
+    /// 保存時に比較する内容を記録
+    pub(crate) fn set_baseline(&mut self, path: &Path, content: String) {
+        self.baseline = Some(Baseline {
+            path: normalize_path(path),
+            content,
+            merge_base: self.to_file_content(self.line_ending()),
+        });
+    }
+
+    /// 指定したパスに対応する記録済みの内容を取得
+    pub(crate) fn baseline_for(&self, path: &Path) -> Option<&Baseline> {
+        self.baseline
+            .as_ref()
+            .filter(|baseline| baseline.path == normalize_path(path))
+    }
+
+    /// 記録済みの内容を破棄する
+    ///
+    /// 以降の保存では、外部での変更を確認せずにファイルを上書きする。
+    pub fn clear_baseline(&mut self) {
+        self.baseline = None;
     }
 
     /// 文字列から TodoList を作成
+    ///
+    /// パースできない行は読み飛ばされる。
+    /// 診断情報が必要な場合は [`TodoList::from_string_with`] を使用する。
     pub fn from_string(content: &str) -> Result<Self> {
-        let mut todos = Vec::new();
+        Self::from_string_with(content, ParsePolicy::Lenient).map(ParseReport::into_list)
+    }
+
+    /// 指定したポリシーで文字列から TodoList を作成
+    pub fn from_string_with(content: &str, policy: ParsePolicy) -> Result<ParseReport> {
+        let mut report = ParseReport::default();
+        report.list.line_ending = LineEnding::detect(content).unwrap_or_default();
+        report.list.final_newline = content.ends_with('\n');
+        let mut offset = 0;
+
+        for (line_num, segment) in content.split_inclusive('\n').enumerate() {
+            let start = offset;
+            offset += segment.len();
 
-        for (line_num, line) in content.lines().enumerate() {
-            let line = line.trim();
+            let line = segment.trim_end_matches('\n').trim_end_matches('\r');
+            let trimmed = line.trim();
 
-            // 空行はスキップ
-            if line.is_empty() {
+            // 空行はタスクにせず、保存時に元の位置へ書き戻す
+            if trimmed.is_empty() {
+                report.list.preserved.push(PreservedLine {
+                    position: report.list.todos.len(),
+                    text: String::new(),
+                });
                 continue;
             }
 
-            match line.parse::<Todo>() {
-                Ok(todo) => todos.push(todo),
-                Err(e) => {
-                    eprintln!("警告: {}行目のパースに失敗しました: {}", line_num + 1, e);
+            let kind = match parse_line(trimmed) {
+                Ok(todo) => {
+                    let index = report.list.len();
+                    report.list.insert_entry(index, todo, None);
+                    continue;
                 }
+                Err(kind) => kind,
+            };
+
+            let leading = line.len() - line.trim_start().len();
+            let diagnostic = ParseDiagnostic {
+                line: line_num + 1,
+                column: line[..leading].chars().count() + 1,
+                span: start + leading..start + leading + trimmed.len(),
+                raw: line.to_string(),
+                kind,
+            };
+
+            match policy {
+                ParsePolicy::Strict => return Err(TodoError::InvalidLine(diagnostic)),
+                ParsePolicy::Lenient => {}
+                ParsePolicy::Preserve => report.list.preserved.push(PreservedLine {
+                    position: report.list.todos.len(),
+                    text: line.to_string(),
+                }),
             }
+            report.diagnostics.push(diagnostic);
         }
 
-        Ok(Self { todos })
-    }
-
-    /// TodoList をファイルに保存
-    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        let content = self.to_string();
-        fs::write(path, content)?;
-        Ok(())
+        Ok(report)
     }
 
-    /// タスクを追加
-    pub fn add(&mut self, todo: Todo) {
-        self.todos.push(todo);
+    /// タスクを追加し、その ID を返す
+    pub fn add(&mut self, todo: Todo) -> TaskId {
+        self.flush_pending();
+        let index = self.todos.len();
+        let id = self.insert_entry(index, todo, None);
+        if self.history.is_enabled() {
+            self.history.record(Operation::Add {
+                index,
+                id: Some(id),
+                todo: self.todos[index].clone(),
+            });
+        }
+        id
     }
 
     /// インデックスでタスクを取得
@@ -62,29 +259,203 @@ impl TodoList {
     }
 
     /// インデックスでタスクを可変参照で取得
+    ///
+    /// 操作履歴が有効な場合、変更は次の操作の前に記録される。
     pub fn get_mut(&mut self, index: usize) -> Option<&mut Todo> {
+        self.flush_pending();
+        let todo = self.todos.get(index)?;
+        if self.history.is_enabled() {
+            self.history.set_pending(index, todo.clone());
+        }
+        if let Some(task_index) = &mut self.task_index {
+            task_index.lend(index, todo);
+        }
         self.todos.get_mut(index)
     }
 
     /// インデックスでタスクを削除
     pub fn remove(&mut self, index: usize) -> Result<Todo> {
+        self.flush_pending();
         if index < self.todos.len() {
-            Ok(self.todos.remove(index))
+            let (todo, id) = self.remove_entry(index);
+            if self.history.is_enabled() {
+                self.history.record(Operation::Remove {
+                    index,
+                    id: Some(id),
+                    todo: todo.clone(),
+                });
+            }
+            Ok(todo)
         } else {
             Err(TodoError::IndexOutOfBounds(index))
         }
     }
 
+    /// 条件に一致するタスクをすべて取り除いて返す
+    pub(crate) fn drain_where<F>(&mut self, mut predicate: F) -> Vec<Todo>
+    where
+        F: FnMut(&Todo) -> bool,
+    {
+        self.flush_pending();
+        let mut drained = Vec::new();
+        let mut drained_entries = Vec::new();
+        let mut kept = Vec::with_capacity(self.todos.len());
+        let mut kept_ids = Vec::with_capacity(self.todos.len());
+        let mut removed_before = Vec::with_capacity(self.todos.len() + 1);
+
+        for (i, (todo, id)) in self.todos.drain(..).zip(self.ids.drain(..)).enumerate() {
+            removed_before.push(drained.len());
+            if predicate(&todo) {
+                drained.push(todo);
+                drained_entries.push((i, id));
+            } else {
+                kept.push(todo);
+                kept_ids.push(id);
+            }
+        }
+        removed_before.push(drained.len());
+
+        if self.history.is_enabled() && !drained.is_empty() {
+            // 後ろから順に削除したものとして記録する
+            let operations = drained_entries
+                .iter()
+                .zip(&drained)
+                .rev()
+                .map(|(&(index, id), todo)| Operation::Remove {
+                    index,
+                    id: Some(id),
+                    todo: todo.clone(),
+                })
+                .collect();
+            self.history.record(Operation::Batch(operations));
+        }
+
+        for line in &mut self.preserved {
+            line.position -= removed_before[line.position];
+        }
+        self.todos = kept;
+        self.ids = kept_ids;
+        self.rebuild_index();
+
+        drained
+    }
+
+    /// インデックスでタスクを完了としてマーク
+    ///
+    /// 完了日にはリストのクロックの日付が使われる。
+    /// 繰り返し指定 (`rec:`) のあるタスクの場合は次のタスクを末尾に追加し、
+    /// そのインデックスを返す。元のタスクに `id:` がある場合、次のタスクには新しい ID を割り当てる。
+    /// すでに完了しているタスクは変更しない。
+    pub fn complete(&mut self, index: usize) -> Result<Option<usize>> {
+        self.flush_pending();
+        let mut operations = Vec::new();
+        let spawned = self.complete_entry(index, &mut operations)?;
+        if !operations.is_empty() {
+            self.history.record(Operation::Batch(operations));
+        }
+        Ok(spawned)
+    }
+
+    /// タスクを完了にし、行った操作を `operations` に追加する（履歴には記録しない）
+    pub(crate) fn complete_entry(
+        &mut self,
+        index: usize,
+        operations: &mut Vec<Operation>,
+    ) -> Result<Option<usize>> {
+        let today = self.today();
+        let todo = self
+            .todos
+            .get(index)
+            .ok_or(TodoError::IndexOutOfBounds(index))?;
+
+        if todo.completed {
+            return Ok(None);
+        }
+
+        let mut next = todo.next_occurrence(today)?;
+        if let Some(next) = next.as_mut()
+            && todo.persistent_id().is_some()
+        {
+            next.add_tag(crate::id::ID_KEY, self.fresh_persistent_id());
+        }
+        let mut completed = todo.clone();
+        completed.complete_on(today);
+
+        operations.push(Operation::Update {
+            index,
+            before: todo.clone(),
+            after: completed.clone(),
+        });
+        self.replace_entry(index, completed);
+
+        let spawned = next.map(|next| {
+            let index = self.todos.len();
+            let id = self.insert_entry(index, next.clone(), None);
+            operations.push(Operation::Add {
+                index,
+                id: Some(id),
+                todo: next,
+            });
+            index
+        });
+
+        Ok(spawned)
+    }
+
     /// すべてのタスクを取得
     pub fn all(&self) -> &[Todo] {
         &self.todos
     }
 
     /// すべてのタスクを可変参照で取得
+    ///
+    /// この参照による変更は操作履歴に記録されない。
     pub fn all_mut(&mut self) -> &mut [Todo] {
+        self.flush_pending();
+        self.invalidate_index();
         &mut self.todos
     }
 
+    /// パースできずにそのまま保持している行を取得（空行は含まない）
+    pub fn preserved_lines(&self) -> impl Iterator<Item = &str> {
+        self.preserved
+            .iter()
+            .map(|line| line.text.as_str())
+            .filter(|text| !text.is_empty())
+    }
+
+    /// 保存時のファイルでの各タスクの行番号 (1 始まり、保持している行も数える)
+    #[cfg(feature = "json")]
+    pub(crate) fn line_numbers(&self) -> Vec<usize> {
+        let mut positions: Vec<usize> = self.preserved.iter().map(|line| line.position).collect();
+        positions.sort_unstable();
+
+        let mut preceding = 0;
+        (0..self.todos.len())
+            .map(|index| {
+                while positions.get(preceding).is_some_and(|&p| p <= index) {
+                    preceding += 1;
+                }
+                index + preceding + 1
+            })
+            .collect()
+    }
+
+    /// インデックスのタスクの ID を取得
+    pub fn id_at(&self, index: usize) -> Option<TaskId> {
+        self.ids.get(index).copied()
+    }
+
+    /// ID のタスクの現在のインデックスを取得
+    pub fn index_of(&self, id: TaskId) -> Option<usize> {
+        self.ids.iter().position(|&i| i == id)
+    }
+
+    /// ID とタスクの組のイテレータを取得
+    pub fn iter_with_ids(&self) -> impl Iterator<Item = (TaskId, &Todo)> {
+        self.ids.iter().copied().zip(&self.todos)
+    }
+
     /// タスクの数を取得
     pub fn len(&self) -> usize {
         self.todos.len()
@@ -101,7 +472,11 @@ impl TodoList {
     }
 
     /// 可変イテレータを取得
+    ///
+    /// このイテレータによる変更は操作履歴に記録されない。
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Todo> {
+        self.flush_pending();
+        self.invalidate_index();
         self.todos.iter_mut()
     }
 
@@ -130,25 +505,80 @@ impl TodoList {
 
     /// 特定のプロジェクトのタスクを取得
     pub fn with_project(&self, project: &str) -> Vec<&Todo> {
-        self.filter(|todo| todo.has_project(project))
+        self.lookup_project(project)
     }
 
     /// 特定のコンテキストのタスクを取得
     pub fn with_context(&self, context: &str) -> Vec<&Todo> {
-        self.filter(|todo| todo.has_context(context))
+        self.lookup_context(context)
+    }
+
+    /// 期限日を過ぎた未完了タスクを取得
+    ///
+    /// 期限日が日付として解釈できないタスクは含まれない。
+    /// それらは [`TodoList::due_date_errors`] で確認できる。
+    pub fn overdue(&self, today: NaiveDate) -> Vec<&Todo> {
+        self.filter(|todo| {
+            !todo.completed && matches!(todo.due_date(), Ok(Some(due)) if due < today)
+        })
+    }
+
+    /// 今日から `days` 日以内（今日を含む）が期限日の未完了タスクを取得
+    pub fn due_within(&self, today: NaiveDate, days: u64) -> Vec<&Todo> {
+        let until = today
+            .checked_add_days(Days::new(days))
+            .unwrap_or(NaiveDate::MAX);
+        self.filter(|todo| {
+            !todo.completed
+                && matches!(todo.due_date(), Ok(Some(due)) if today <= due && due <= until)
+        })
+    }
+
+    /// 期限日 (`due:`) が日付として解釈できないタスクのインデックスとエラーを取得
+    pub fn due_date_errors(&self) -> Vec<(usize, TodoError)> {
+        self.todos
+            .iter()
+            .enumerate()
+            .filter_map(|(i, todo)| todo.due_date().err().map(|e| (i, e)))
+            .collect()
+    }
+
+    /// 指定日に着手可能なタスクを取得
+    ///
+    /// 完了済みのタスクと、着手可能日 (`t:`) が指定日より後のタスクは含まれない。
+    /// プロジェクトやコンテキストと組み合わせる場合は [`Todo::is_actionable`] を使用する:
+    ///
+    /// ```
+    /// # use todo_rs::TodoList;
+    /// # use chrono::NaiveDate;
+    /// let list = TodoList::from_string("Write report +Work t:2024-11-10\nCall Bob +Work").unwrap();
+    /// let today = NaiveDate::from_ymd_opt(2024, 11, 5).unwrap();
+    ///
+    /// let work = list.filter(|todo| todo.is_actionable(today) && todo.has_project("Work"));
+    /// assert_eq!(work.len(), 1);
+    /// ```
+    pub fn actionable(&self, today: NaiveDate) -> Vec<&Todo> {
+        self.filter(|todo| todo.is_actionable(today))
     }
 
     /// タスクをソート
-    pub fn sort_by<F>(&mut self, compare: F)
+    pub fn sort_by<F>(&mut self, mut compare: F)
     where
         F: FnMut(&Todo, &Todo) -> std::cmp::Ordering,
     {
-        self.todos.sort_by(compare);
+        self.flush_pending();
+        let mut order: Vec<usize> = (0..self.todos.len()).collect();
+        order.sort_by(|&a, &b| compare(&self.todos[a], &self.todos[b]));
+
+        if order.iter().enumerate().any(|(new, &old)| new != old) {
+            self.permute_entries(&order);
+            self.history.record(Operation::Reorder { order });
+        }
     }
 
     /// 優先度でソート（高い優先度が先）
     pub fn sort_by_priority(&mut self) {
-        self.todos.sort_by(|a, b| match (a.priority, b.priority) {
+        self.sort_by(|a, b| match (a.priority, b.priority) {
             (Some(p1), Some(p2)) => p1.cmp(&p2),
             (Some(_), None) => std::cmp::Ordering::Less,
             (None, Some(_)) => std::cmp::Ordering::Greater,
@@ -158,37 +588,198 @@ impl TodoList {
 
     /// 作成日でソート（新しい順）
     pub fn sort_by_creation_date(&mut self) {
-        self.todos
-            .sort_by(|a, b| match (a.creation_date, b.creation_date) {
-                (Some(d1), Some(d2)) => d2.cmp(&d1),
-                (Some(_), None) => std::cmp::Ordering::Less,
-                (None, Some(_)) => std::cmp::Ordering::Greater,
-                (None, None) => std::cmp::Ordering::Equal,
-            });
+        self.sort_by(|a, b| match (a.creation_date, b.creation_date) {
+            (Some(d1), Some(d2)) => d2.cmp(&d1),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+    }
+
+    /// 期限日でソート（近い順、期限日なしは最後）
+    pub fn sort_by_due_date(&mut self) {
+        let key = |todo: &Todo| match todo.due_date() {
+            Ok(Some(date)) => (false, date),
+            _ => (true, NaiveDate::MIN),
+        };
+        self.sort_by(|a, b| key(a).cmp(&key(b)));
     }
 
     /// 説明でソート（辞書順）
     pub fn sort_by_description(&mut self) {
-        self.todos.sort_by(|a, b| a.description.cmp(&b.description));
+        self.sort_by(|a, b| a.description.cmp(&b.description));
+    }
+
+    /// タスクと保持している行を別のリストの内容で置き換える
+    ///
+    /// インデックスの対応が失われるため、操作履歴は消去される。
+    /// 内容が同じタスクには元の ID を引き継ぐ。
+    pub(crate) fn replace_entries(&mut self, other: TodoList) {
+        let mut previous: HashMap<String, Vec<TaskId>> = HashMap::new();
+        for (todo, &id) in self.todos.iter().zip(&self.ids).rev() {
+            previous.entry(todo.to_string()).or_default().push(id);
+        }
+
+        self.todos.clear();
+        self.ids.clear();
+        if let Some(task_index) = &mut self.task_index {
+            *task_index = TaskIndex::default();
+        }
+        self.preserved = other.preserved;
+        for todo in other.todos {
+            let id = previous.get_mut(&todo.to_string()).and_then(Vec::pop);
+            let index = self.todos.len();
+            self.insert_entry(index, todo, id);
+        }
+        self.clear_history();
+    }
+
+    pub(crate) fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub(crate) fn history_mut(&mut self) -> &mut History {
+        &mut self.history
+    }
+
+    /// 履歴に記録せずにタスクを挿入する
+    ///
+    /// `id` が `None` の場合は新しい ID を割り当てる。
+    pub(crate) fn insert_entry(&mut self, index: usize, todo: Todo, id: Option<TaskId>) -> TaskId {
+        let id = id.unwrap_or_else(|| self.next_task_id());
+        for line in &mut self.preserved {
+            if line.position > index {
+                line.position += 1;
+            }
+        }
+        self.sync_index();
+        if let Some(task_index) = &mut self.task_index {
+            task_index.inserted(index, &todo);
+        }
+        self.todos.insert(index, todo);
+        self.ids.insert(index, id);
+        id
+    }
+
+    /// 履歴に記録せずにタスクを削除する
+    pub(crate) fn remove_entry(&mut self, index: usize) -> (Todo, TaskId) {
+        for line in &mut self.preserved {
+            if line.position > index {
+                line.position -= 1;
+            }
+        }
+        self.sync_index();
+        if let Some(task_index) = &mut self.task_index {
+            task_index.removed(index, &self.todos[index]);
+        }
+        (self.todos.remove(index), self.ids.remove(index))
+    }
+
+    /// 履歴に記録せずにタスクを置き換える
+    pub(crate) fn replace_entry(&mut self, index: usize, todo: Todo) -> Todo {
+        self.sync_index();
+        if let Some(task_index) = &mut self.task_index {
+            task_index.replaced(index, &self.todos[index], &todo);
+        }
+        std::mem::replace(&mut self.todos[index], todo)
+    }
+
+    /// 履歴に記録せずにタスクを並べ替える（新しい `i` 番目は元の `order[i]` 番目）
+    pub(crate) fn permute_entries(&mut self, order: &[usize]) {
+        let mut old: Vec<Option<Todo>> = self.todos.drain(..).map(Some).collect();
+        self.todos = order.iter().map(|&i| old[i].take().unwrap()).collect();
+        self.ids = order.iter().map(|&i| self.ids[i]).collect();
+        self.rebuild_index();
+    }
+
+    pub(crate) fn task_index(&self) -> &Option<TaskIndex> {
+        &self.task_index
+    }
+
+    pub(crate) fn task_index_mut(&mut self) -> &mut Option<TaskIndex> {
+        &mut self.task_index
+    }
+
+    /// 貸し出し中のタスクの変更などを索引に反映する
+    pub(crate) fn sync_index(&mut self) {
+        if let Some(task_index) = &mut self.task_index {
+            task_index.sync(&self.todos);
+        }
+    }
+
+    /// 索引を作り直す
+    fn rebuild_index(&mut self) {
+        if let Some(task_index) = &mut self.task_index {
+            *task_index = TaskIndex::build(&self.todos);
+        }
     }
+
+    /// どのタスクが変更されるか分からない場合に、索引を次の操作で作り直すようにする
+    fn invalidate_index(&mut self) {
+        if let Some(task_index) = &mut self.task_index {
+            task_index.invalidate();
+        }
+    }
+
+    /// 新しい ID を発行する
+    fn next_task_id(&mut self) -> TaskId {
+        let id = TaskId::new(self.next_id);
+        self.next_id += 1;
+        id
+    }
+}
+
+/// 同じファイルを指すパスを比較できるように正規化する
+fn normalize_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
 }
 
 impl fmt::Display for TodoList {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let content = self
-            .todos
-            .iter()
-            .map(|todo| todo.to_string())
-            .collect::<Vec<_>>()
-            .join("\n");
-        write!(f, "{}", content)
+        let mut lines = Vec::with_capacity(self.todos.len() + self.preserved.len());
+        let mut preserved = self.preserved.iter().peekable();
+
+        for (i, todo) in self.todos.iter().enumerate() {
+            while let Some(line) = preserved.next_if(|line| line.position <= i) {
+                lines.push(line.text.clone());
+            }
+            lines.push(todo.to_string());
+        }
+        lines.extend(preserved.map(|line| line.text.clone()));
+
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl Extend<Todo> for TodoList {
+    fn extend<T: IntoIterator<Item = Todo>>(&mut self, iter: T) {
+        for todo in iter {
+            self.add(todo);
+        }
+    }
+}
+
+impl FromIterator<Todo> for TodoList {
+    fn from_iter<T: IntoIterator<Item = Todo>>(iter: T) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl IntoIterator for TodoList {
+    type Item = Todo;
+    type IntoIter = std::vec::IntoIter<Todo>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.todos.into_iter()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Priority;
+    use crate::{FixedClock, Priority};
 
     #[test]
     fn test_new_list() {
@@ -219,6 +810,48 @@ mod tests {
         assert_eq!(list.get(0).unwrap().description, "Task 2");
     }
 
+    #[test]
+    fn test_complete_recurring() {
+        let mut list = TodoList::from_string("Water plants +Home rec:3d\nCall Mom")
+            .unwrap()
+            .with_clock(FixedClock::new(date(2024, 11, 5)));
+
+        let spawned = list.complete(0).unwrap();
+        assert_eq!(spawned, Some(2));
+        assert!(list.get(0).unwrap().completed);
+        assert_eq!(
+            list.get(0).unwrap().completion_date,
+            Some(date(2024, 11, 5))
+        );
+
+        let next = list.get(2).unwrap();
+        assert!(!next.completed);
+        assert_eq!(next.to_string(), "2024-11-05 Water plants +Home rec:3d");
+
+        assert_eq!(list.complete(1).unwrap(), None);
+        assert_eq!(list.complete(0).unwrap(), None);
+        assert_eq!(list.len(), 3);
+        assert!(list.complete(5).is_err());
+    }
+
+    #[test]
+    fn test_complete_recurring_task_with_id() {
+        let mut list =
+            TodoList::from_string("Water plants id:7 rec:1w\nBuy pots dep:7\nRepot id:8")
+                .unwrap()
+                .with_clock(FixedClock::new(date(2024, 11, 7)));
+        let next = list.complete(0).unwrap().unwrap();
+
+        assert_eq!(list.get(next).unwrap().persistent_id(), Some("9"));
+        let ready: Vec<&str> = list
+            .ready()
+            .unwrap()
+            .into_iter()
+            .map(|todo| todo.description.as_str())
+            .collect();
+        assert_eq!(ready, ["Buy pots", "Repot", "Water plants"]);
+    }
+
     #[test]
     fn test_filter_completed() {
         let mut list = TodoList::new();
@@ -259,6 +892,68 @@ mod tests {
         assert_eq!(list.get(2).unwrap().priority.unwrap().as_char(), 'C');
     }
 
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_clock() {
+        let list = TodoList::new().with_clock(FixedClock::new(date(2024, 11, 5)));
+        assert_eq!(list.today(), date(2024, 11, 5));
+        assert_eq!(list.clone().clock().today(), date(2024, 11, 5));
+    }
+
+    #[test]
+    fn test_due_date_queries() {
+        let content = "Late due:2024-11-01\nToday due:2024-11-05\nSoon due:2024-11-08\n\
+                       Later due:2024-12-01\nx Done due:2024-11-01\nBroken due:someday";
+        let list = TodoList::from_string(content).unwrap();
+        let today = date(2024, 11, 5);
+
+        let overdue = list.overdue(today);
+        assert_eq!(overdue.len(), 1);
+        assert_eq!(overdue[0].description, "Late");
+
+        let soon: Vec<_> = list
+            .due_within(today, 3)
+            .iter()
+            .map(|todo| todo.description.as_str())
+            .collect();
+        assert_eq!(soon, ["Today", "Soon"]);
+
+        let errors = list.due_date_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, 5);
+    }
+
+    #[test]
+    fn test_actionable() {
+        let content = "Now +Work @office\nLater +Work t:2024-11-10\n\
+                       Started t:2024-11-05 @office\nx Done";
+        let list = TodoList::from_string(content).unwrap();
+        let today = date(2024, 11, 5);
+
+        let actionable: Vec<_> = list
+            .actionable(today)
+            .iter()
+            .map(|todo| todo.description.as_str())
+            .collect();
+        assert_eq!(actionable, ["Now", "Started"]);
+
+        let office = list.filter(|todo| todo.is_actionable(today) && todo.has_context("office"));
+        assert_eq!(office.len(), 2);
+    }
+
+    #[test]
+    fn test_sort_by_due_date() {
+        let content = "No due\nB due:2024-11-10\nBroken due:someday\nA due:2024-11-01";
+        let mut list = TodoList::from_string(content).unwrap();
+        list.sort_by_due_date();
+
+        let order: Vec<_> = list.iter().map(|todo| todo.description.as_str()).collect();
+        assert_eq!(order, ["A", "B", "No due", "Broken"]);
+    }
+
     #[test]
     fn test_from_string() {
         let content = "(A) Task 1\n(B) Task 2 +Project @context\nx 2024-11-03 Task 3";
@@ -268,6 +963,97 @@ mod tests {
         assert!(list.get(2).unwrap().completed);
     }
 
+    #[test]
+    fn test_roundtrip_keeps_untouched_lines() {
+        let content = "Call +Mom about @phone bill\n(B) Write report +Work due:2024-11-10 today";
+        let mut list = TodoList::from_string(content).unwrap();
+        list.get_mut(1).unwrap().add_tag("due", "2024-11-11");
+
+        assert_eq!(
+            list.to_string(),
+            "Call +Mom about @phone bill\n(B) Write report +Work due:2024-11-11 today"
+        );
+    }
+
+    #[test]
+    fn test_from_string_lenient_reports_bad_lines() {
+        let content = "Task 1\n  x 2024-11-03 due:2024-11-10\nTask 2";
+        let report = TodoList::from_string_with(content, ParsePolicy::Lenient).unwrap();
+
+        assert_eq!(report.list.len(), 2);
+        assert_eq!(report.diagnostics.len(), 1);
+
+        let diagnostic = &report.diagnostics[0];
+        assert_eq!(diagnostic.line, 2);
+        assert_eq!(diagnostic.column, 3);
+        assert_eq!(
+            &content[diagnostic.span.clone()],
+            "x 2024-11-03 due:2024-11-10"
+        );
+        assert_eq!(diagnostic.raw, "  x 2024-11-03 due:2024-11-10");
+        assert_eq!(diagnostic.kind, crate::ParseErrorKind::EmptyContent);
+    }
+
+    #[test]
+    fn test_from_string_strict_fails() {
+        let content = "Task 1\r\n(A)\r\n";
+        let err = TodoList::from_string_with(content, ParsePolicy::Strict).unwrap_err();
+
+        match err {
+            TodoError::InvalidLine(diagnostic) => {
+                assert_eq!(diagnostic.line, 2);
+                assert_eq!(diagnostic.span, 8..11);
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    fn test_from_string_preserve_writes_back() {
+        let content = "(A)\nTask 1\nx 2024-11-03\nTask 2\ndue:2024-11-10";
+        let report = TodoList::from_string_with(content, ParsePolicy::Preserve).unwrap();
+        assert_eq!(report.diagnostics.len(), 3);
+
+        let mut list = report.into_list();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.to_string(), content);
+
+        list.remove(0).unwrap();
+        assert_eq!(
+            list.to_string(),
+            "(A)\nx 2024-11-03\nTask 2\ndue:2024-11-10"
+        );
+    }
+
+    #[test]
+    fn test_drain_where_keeps_preserved_positions() {
+        let content = "Task 1\nx 2024-11-03 Task 2\n(A)\nTask 3\nx 2024-11-04 Task 4\n(B)";
+        let mut list = TodoList::from_string_with(content, ParsePolicy::Preserve)
+            .unwrap()
+            .into_list();
+
+        let drained = list.drain_where(|todo| todo.completed);
+        assert_eq!(drained.len(), 2);
+        assert_eq!(list.to_string(), "Task 1\n(A)\nTask 3\n(B)");
+    }
+
+    #[test]
+    fn test_blank_lines_are_kept() {
+        let list = TodoList::from_string("Task 1\n\n  \nTask 2\n").unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.preserved_lines().count(), 0);
+        assert_eq!(list.to_string(), "Task 1\n\n\nTask 2");
+    }
+
+    #[test]
+    fn test_collect_and_extend() {
+        let mut list: TodoList = vec![Todo::new("Task 1")].into_iter().collect();
+        list.extend(vec![Todo::new("Task 2")]);
+
+        let descriptions: Vec<_> = list.into_iter().map(|todo| todo.description).collect();
+        assert_eq!(descriptions, ["Task 1", "Task 2"]);
+    }
+
     #[test]
     fn test_to_string() {
         let mut list = TodoList::new();
@@ -0,0 +1,536 @@
+use crate::{Result, SaveOptions, TodoError, TodoList};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// ロックの方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LockMethod {
+    /// `todo.txt.lock` に対する OS のファイルロック (Linux では flock) を使い、
+    /// ファイルシステムが対応していない場合はロックファイルにフォールバックする
+    #[default]
+    Auto,
+    /// `todo.txt.lck` を排他的に作成するロックファイルのみを使う
+    ///
+    /// ロックファイルには保持しているプロセスの PID とホスト名が書き込まれる。
+    /// 同じホストでそのプロセスが終了している場合 (Linux のみ判定できる) や、
+    /// [`LockOptions::stale_after`] より古い場合は、残ったロックファイルを引き継いで取得する。
+    /// 引き継ぎはロックファイル自体への OS のファイルロックで保護するため、
+    /// それが使えないファイルシステムでは行わない。
+    LockFile,
+}
+
+/// ロック取得時のオプション
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockOptions {
+    /// ロックを待つ最大時間
+    pub timeout: Duration,
+
+    /// ロックを再試行する間隔
+    pub retry_interval: Duration,
+
+    /// ロックの方式
+    pub method: LockMethod,
+
+    /// これより古いロックファイルを、異常終了したプロセスが残したものとみなす時間
+    ///
+    /// `None` の場合は時間では判定しない。OS のファイルロックには影響しない。
+    pub stale_after: Option<Duration>,
+}
+
+impl Default for LockOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LockOptions {
+    /// デフォルトのオプションを作成
+    ///
+    /// 最大 5 秒間、50 ミリ秒ごとに再試行する。
+    pub fn new() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            retry_interval: Duration::from_millis(50),
+            method: LockMethod::Auto,
+            stale_after: None,
+        }
+    }
+
+    /// ロックを待つ最大時間を設定
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// ロックを再試行する間隔を設定
+    pub fn with_retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    /// ロックの方式を設定
+    pub fn with_method(mut self, method: LockMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// 残ったロックファイルを削除するまでの時間を設定
+    pub fn with_stale_after(mut self, duration: Duration) -> Self {
+        self.stale_after = Some(duration);
+        self
+    }
+}
+
+/// ロックを保持したまま読み込んだ TodoList
+///
+/// ドロップ時（または [`LockedTodoList::unlock`] の呼び出し時）にロックが解放される。
+///
+/// ```no_run
+/// # use todo_rs::{LockOptions, LockedTodoList, Todo};
+/// let mut list = LockedTodoList::open("todo.txt", &LockOptions::new())?;
+/// list.add(Todo::new("Call Mom"));
+/// list.save()?;
+/// # Ok::<(), todo_rs::TodoError>(())
+/// ```
+#[derive(Debug)]
+pub struct LockedTodoList {
+    list: TodoList,
+    path: PathBuf,
+    _lock: FileLock,
+}
+
+impl LockedTodoList {
+    /// ファイルをロックして TodoList を読み込み
+    ///
+    /// ファイルが存在しない場合は空のリストになり、保存時に作成される。
+    /// タイムアウトまでにロックを取得できない場合は [`TodoError::LockTimeout`] を返す。
+    pub fn open<P: AsRef<Path>>(path: P, options: &LockOptions) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let lock = FileLock::acquire(&path, options)?;
+
+        let list = match TodoList::from_file(&path) {
+            Ok(list) => list,
+            Err(TodoError::IoError(e)) if e.kind() == io::ErrorKind::NotFound => TodoList::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            list,
+            path,
+            _lock: lock,
+        })
+    }
+
+    /// ロックしているファイルのパスを取得
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// ロックしているファイルに保存
+    pub fn save(&mut self) -> Result<()> {
+        self.list.save_to_file(&self.path)
+    }
+
+    /// オプションを指定して、ロックしているファイルに保存
+    pub fn save_with(&mut self, options: &SaveOptions) -> Result<()> {
+        self.list.save_to_file_with(&self.path, options)
+    }
+
+    /// ロックを解放して TodoList を取り出す
+    pub fn unlock(self) -> TodoList {
+        self.list
+    }
+}
+
+impl Deref for LockedTodoList {
+    type Target = TodoList;
+
+    fn deref(&self) -> &TodoList {
+        &self.list
+    }
+}
+
+impl DerefMut for LockedTodoList {
+    fn deref_mut(&mut self) -> &mut TodoList {
+        &mut self.list
+    }
+}
+
+impl TodoList {
+    /// ファイルをロックして読み込み、`f` で変更して保存した後にロックを解放する
+    ///
+    /// `f` がエラーを返した場合は保存しない。
+    pub fn with_locked_file<P, F, T>(path: P, options: &LockOptions, f: F) -> Result<T>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&mut TodoList) -> Result<T>,
+    {
+        let mut locked = LockedTodoList::open(path, options)?;
+        let value = f(&mut locked)?;
+        locked.save()?;
+        Ok(value)
+    }
+}
+
+/// 取得済みのロック
+#[derive(Debug)]
+enum FileLock {
+    /// OS のファイルロック
+    Os(File),
+    /// 排他的に作成したロックファイル（削除すると解放される）
+    LockFile(PathBuf),
+}
+
+impl FileLock {
+    fn acquire(path: &Path, options: &LockOptions) -> Result<Self> {
+        let started = Instant::now();
+        let mut method = options.method;
+
+        loop {
+            let attempt = match method {
+                LockMethod::Auto => try_os_lock(path),
+                LockMethod::LockFile => try_lock_file(path, options.stale_after),
+            };
+
+            match attempt {
+                Ok(Some(lock)) => return Ok(lock),
+                Ok(None) => {}
+                Err(e) if method == LockMethod::Auto && e.kind() == io::ErrorKind::Unsupported => {
+                    method = LockMethod::LockFile;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            }
+
+            let elapsed = started.elapsed();
+            if elapsed >= options.timeout {
+                return Err(TodoError::LockTimeout(path.to_path_buf()));
+            }
+            thread::sleep(options.retry_interval.min(options.timeout - elapsed));
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        match self {
+            FileLock::Os(file) => {
+                let _ = file.unlock();
+            }
+            FileLock::LockFile(path) => {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+}
+
+/// `path` に拡張子を追加したパス
+fn sibling(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+/// OS のファイルロックを試みる（他で保持されている場合は `None`）
+///
+/// todo.txt は保存時に置き換えられるため、別のファイルをロックする。
+fn try_os_lock(path: &Path) -> io::Result<Option<FileLock>> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(sibling(path, ".lock"))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(Some(FileLock::Os(file))),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// ロックファイルの作成を試みる（他のプロセスが保持している場合は `None`）
+fn try_lock_file(path: &Path, stale_after: Option<Duration>) -> io::Result<Option<FileLock>> {
+    let lock_path = sibling(path, ".lck");
+
+    let acquired = match create_lock_file(&lock_path) {
+        Ok(()) => true,
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            take_over_stale_lock(&lock_path, stale_after)?
+        }
+        Err(e) => return Err(e),
+    };
+    // 取得できなかった場合に `FileLock` を作ると、破棄時に他のロックファイルを削除してしまう
+    Ok(if acquired {
+        Some(FileLock::LockFile(lock_path))
+    } else {
+        None
+    })
+}
+
+/// ロックファイルを排他的に作成し、保持者を表す内容を書き込む
+fn create_lock_file(lock_path: &Path) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(lock_path)?;
+    file.write_all(lock_content().as_bytes())
+}
+
+/// ロックファイルの内容（PID、ホスト名、取得ごとに異なるトークン）
+fn lock_content() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    format!(
+        "{}\n{}\n{}-{}\n",
+        std::process::id(),
+        host_name().unwrap_or_default(),
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// 異常終了したプロセスが残したロックファイルを引き継ぐ（引き継げた場合は `true`）
+///
+/// 古さの判定から書き換えまで、ロックファイル自体を OS のファイルロックで保護する。
+/// 削除して作り直すのではなく同じファイルに書き込むため、複数の待機者が同時に
+/// 古いと判定しても引き継げるのは 1 つだけになる。
+/// OS のファイルロックが使えない場合は引き継がない。
+fn take_over_stale_lock(lock_path: &Path, stale_after: Option<Duration>) -> io::Result<bool> {
+    let mut file = match OpenOptions::new().read(true).write(true).open(lock_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => return Ok(false),
+        Err(TryLockError::Error(e)) if e.kind() == io::ErrorKind::Unsupported => return Ok(false),
+        Err(TryLockError::Error(e)) => return Err(e),
+    }
+
+    let mut content = String::new();
+    file.read_to_string(&mut content)?;
+    let modified = file.metadata()?.modified().ok();
+    if !is_stale(&content, modified, stale_after) {
+        return Ok(false);
+    }
+
+    let ours = lock_content();
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(ours.as_bytes())?;
+
+    // 開いてからロックするまでの間に削除されていた場合、書き込んだファイルはもう使われていない
+    Ok(fs::read_to_string(lock_path).is_ok_and(|current| current == ours))
+}
+
+/// ロックファイルが異常終了したプロセスの残したものかチェック
+fn is_stale(content: &str, modified: Option<SystemTime>, stale_after: Option<Duration>) -> bool {
+    if let Some(limit) = stale_after
+        && let Some(modified) = modified
+        && SystemTime::now()
+            .duration_since(modified)
+            .is_ok_and(|age| age >= limit)
+    {
+        return true;
+    }
+
+    // 別のホストの PID は確認できないため、同じホストの場合だけ判定する
+    let mut lines = content.lines();
+    let pid = lines.next().and_then(|pid| pid.trim().parse::<u32>().ok());
+    let host = lines.next().map(str::trim);
+    match (pid, host, host_name()) {
+        (Some(pid), Some(host), Some(current)) if host == current => {
+            process_exists(pid) == Some(false)
+        }
+        _ => false,
+    }
+}
+
+/// ホスト名（取得できない場合は `None`）
+#[cfg(target_os = "linux")]
+fn host_name() -> Option<String> {
+    let name = fs::read_to_string("/proc/sys/kernel/hostname").ok()?;
+    Some(name.trim().to_string()).filter(|name| !name.is_empty())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn host_name() -> Option<String> {
+    None
+}
+
+/// プロセスが存在するかチェック（判定できない場合は `None`）
+#[cfg(target_os = "linux")]
+fn process_exists(pid: u32) -> Option<bool> {
+    Some(Path::new("/proc").join(pid.to_string()).exists())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_exists(_pid: u32) -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Todo;
+    use crate::test_util::temp_dir;
+
+    fn quick() -> LockOptions {
+        LockOptions::new()
+            .with_timeout(Duration::from_millis(30))
+            .with_retry_interval(Duration::from_millis(5))
+    }
+
+    #[test]
+    fn test_locked_session() {
+        let dir = temp_dir("lock-session");
+        let path = dir.join("todo.txt");
+
+        let mut list = LockedTodoList::open(&path, &quick()).unwrap();
+        assert!(list.is_empty());
+        list.add(Todo::new("Task 1"));
+        list.save().unwrap();
+
+        assert!(matches!(
+            LockedTodoList::open(&path, &quick()),
+            Err(TodoError::LockTimeout(_))
+        ));
+
+        let list = list.unlock();
+        assert_eq!(list.len(), 1);
+
+        let list = LockedTodoList::open(&path, &quick()).unwrap();
+        assert_eq!(list.get(0).unwrap().description, "Task 1");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_lock_file_method() {
+        let dir = temp_dir("lock-file");
+        let path = dir.join("todo.txt");
+        let options = quick().with_method(LockMethod::LockFile);
+
+        let list = LockedTodoList::open(&path, &options).unwrap();
+        assert!(dir.join("todo.txt.lck").exists());
+        assert!(matches!(
+            LockedTodoList::open(&path, &options),
+            Err(TodoError::LockTimeout(_))
+        ));
+
+        drop(list);
+        assert!(!dir.join("todo.txt.lck").exists());
+        assert!(LockedTodoList::open(&path, &options).is_ok());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_stale_lock_file_by_age() {
+        let dir = temp_dir("lock-stale-age");
+        let path = dir.join("todo.txt");
+        let lock_path = dir.join("todo.txt.lck");
+        fs::write(&lock_path, "1\nsome-other-host\n").unwrap();
+
+        let options = quick().with_method(LockMethod::LockFile);
+        assert!(matches!(
+            LockedTodoList::open(&path, &options),
+            Err(TodoError::LockTimeout(_))
+        ));
+
+        let options = options.with_stale_after(Duration::ZERO);
+        let list = LockedTodoList::open(&path, &options).unwrap();
+        let content = fs::read_to_string(&lock_path).unwrap();
+        assert_eq!(
+            content.lines().next(),
+            Some(std::process::id().to_string().as_str())
+        );
+        drop(list);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_stale_lock_file_by_pid() {
+        let dir = temp_dir("lock-stale-pid");
+        let path = dir.join("todo.txt");
+        let lock_path = dir.join("todo.txt.lck");
+        let host = host_name().unwrap();
+        let options = quick().with_method(LockMethod::LockFile);
+
+        // 自分自身の PID は生きているので取得できない
+        fs::write(&lock_path, format!("{}\n{}\n", std::process::id(), host)).unwrap();
+        assert!(matches!(
+            LockedTodoList::open(&path, &options),
+            Err(TodoError::LockTimeout(_))
+        ));
+
+        // pid_max (最大 2^22) を超える PID のプロセスは存在しない
+        fs::write(&lock_path, format!("{}\n{}\n", u32::MAX, host)).unwrap();
+        assert!(LockedTodoList::open(&path, &options).is_ok());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_stale_lock_file_taken_over_once() {
+        let dir = temp_dir("lock-stale-race");
+        let path = dir.join("todo.txt");
+        let lock_path = dir.join("todo.txt.lck");
+        let hour = Duration::from_secs(3600);
+
+        for _ in 0..20 {
+            fs::write(&lock_path, "1\nsome-other-host\n").unwrap();
+            File::options()
+                .write(true)
+                .open(&lock_path)
+                .unwrap()
+                .set_modified(SystemTime::now() - 2 * hour)
+                .unwrap();
+
+            let barrier = std::sync::Barrier::new(2);
+            let locks: Vec<Option<FileLock>> = thread::scope(|scope| {
+                let handles: Vec<_> = (0..2)
+                    .map(|_| {
+                        scope.spawn(|| {
+                            barrier.wait();
+                            try_lock_file(&path, Some(hour)).unwrap()
+                        })
+                    })
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            });
+            assert_eq!(locks.iter().filter(|lock| lock.is_some()).count(), 1);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_with_locked_file() {
+        let dir = temp_dir("lock-closure");
+        let path = dir.join("todo.txt");
+        fs::write(&path, "Task 1\n").unwrap();
+
+        let count = TodoList::with_locked_file(&path, &quick(), |list| {
+            list.add(Todo::new("Task 2"));
+            Ok(list.len())
+        })
+        .unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Task 1\nTask 2\n");
+
+        let result: Result<()> = TodoList::with_locked_file(&path, &quick(), |list| {
+            list.add(Todo::new("Task 3"));
+            Err(TodoError::IndexOutOfBounds(0))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Task 1\nTask 2\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
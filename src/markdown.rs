@@ -0,0 +1,519 @@
+use crate::id::ID_KEY;
+use crate::parser::parse_line;
+use crate::{GroupBy, ParseDiagnostic, ParseReport, Todo, TodoList};
+use std::collections::{BTreeMap, HashSet};
+
+/// 入れ子になったリスト項目の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NestedItems {
+    /// `id:` / `parent:` タグによる親子関係として扱う
+    #[default]
+    Subtasks,
+    /// 親子関係を持たない平坦なリストとして扱う
+    Flatten,
+}
+
+/// Markdown のチェックリストの設定
+///
+/// ```
+/// use todo_rs::{GroupBy, MarkdownOptions, TodoList};
+///
+/// let list = TodoList::from_string("Write spec +Docs\nx Fix typo +Docs\nBuy milk").unwrap();
+/// let options = MarkdownOptions::new().with_group_by(GroupBy::Project);
+/// assert_eq!(
+///     list.to_markdown(&options),
+///     "## +Docs\n\n- [ ] Write spec +Docs\n- [x] Fix typo +Docs\n\n## (なし)\n\n- [ ] Buy milk\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownOptions {
+    /// 見出しによるグループ分け（`None` の場合は見出しを付けない）
+    pub group_by: Option<GroupBy>,
+
+    /// 見出しのレベル (1〜6)
+    pub heading_level: usize,
+
+    /// 入れ子の項目の扱い
+    pub nested: NestedItems,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            group_by: None,
+            heading_level: 2,
+            nested: NestedItems::default(),
+        }
+    }
+}
+
+impl MarkdownOptions {
+    /// デフォルトの設定を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 見出しによるグループ分けを設定
+    pub fn with_group_by(mut self, group_by: GroupBy) -> Self {
+        self.group_by = Some(group_by);
+        self
+    }
+
+    /// 見出しのレベルを設定（1〜6 に丸められる）
+    pub fn with_heading_level(mut self, level: usize) -> Self {
+        self.heading_level = level.clamp(1, 6);
+        self
+    }
+
+    /// 入れ子の項目の扱いを設定
+    pub fn with_nested(mut self, nested: NestedItems) -> Self {
+        self.nested = nested;
+        self
+    }
+
+    /// グループの見出しの文字列
+    fn heading(&self, key: Option<&str>) -> String {
+        let text = match (&self.group_by, key) {
+            (_, None) => "(なし)".to_string(),
+            (Some(GroupBy::Project), Some(key)) => format!("+{}", key),
+            (Some(GroupBy::Context), Some(key)) => format!("@{}", key),
+            (_, Some(key)) => key.to_string(),
+        };
+        format!("{} {}", "#".repeat(self.heading_level), text)
+    }
+}
+
+impl TodoList {
+    /// Markdown のチェックリスト (`- [ ]` / `- [x]`) として出力
+    ///
+    /// 各項目の本文は todo.txt の行から完了マーカー `x` を除いたもので、
+    /// 優先度や日付、タグはそのまま残る。
+    /// [`NestedItems::Subtasks`] の場合は子タスクを親の下に字下げして並べる。
+    ///
+    /// グループ分けを指定した場合は、最初のキー（プロジェクトなど）の見出しの下に並べ、
+    /// 該当するキーのないタスクは最後の「(なし)」の見出しに入る。
+    /// 複数のキーを持つタスクも一度だけ出力されるので、[`TodoList::from_markdown`] で元に戻せる。
+    /// 子タスクは親と同じ見出しに入る。
+    pub fn to_markdown(&self, options: &MarkdownOptions) -> String {
+        // (深さ, タスク) の並びを、先頭のタスクのキーでまとめた単位に分ける
+        let mut units: Vec<Vec<(usize, &Todo)>> = Vec::new();
+        match options.nested {
+            NestedItems::Subtasks => {
+                for node in self.tree() {
+                    if node.depth == 0 || units.is_empty() {
+                        units.push(Vec::new());
+                    }
+                    if let Some(unit) = units.last_mut() {
+                        unit.push((node.depth, node.todo));
+                    }
+                }
+            }
+            NestedItems::Flatten => units.extend(self.iter().map(|todo| vec![(0, todo)])),
+        }
+
+        let mut out = String::new();
+        let Some(group_by) = &options.group_by else {
+            for unit in &units {
+                write_items(&mut out, unit);
+            }
+            return out;
+        };
+
+        let mut groups: BTreeMap<String, Vec<&[(usize, &Todo)]>> = BTreeMap::new();
+        let mut rest = Vec::new();
+        for unit in &units {
+            match group_by.keys(unit[0].1).into_iter().next() {
+                Some(key) => groups.entry(key).or_default().push(unit),
+                None => rest.push(unit.as_slice()),
+            }
+        }
+
+        let groups = groups
+            .iter()
+            .map(|(key, units)| (Some(key.as_str()), units))
+            .chain((!rest.is_empty()).then_some((None, &rest)));
+        for (key, units) in groups {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&options.heading(key));
+            out.push_str("\n\n");
+            for unit in units {
+                write_items(&mut out, unit);
+            }
+        }
+        out
+    }
+
+    /// Markdown のチェックリストからタスクを読み込む
+    ///
+    /// `- [ ]`、`* [x]`、`1. [ ]` のようなチェックボックス付きの項目だけを読み、
+    /// 見出しや本文、コードブロックは無視する。項目の本文は todo.txt の行としてパースするので、
+    /// 優先度や日付、タグを書いておけば反映される。完了状態はチェックボックスに従う。
+    ///
+    /// [`NestedItems::Subtasks`] の場合、字下げされた項目は直前の浅い項目の子タスクになる。
+    /// `id:` のない親には数値の ID を割り当て、`parent:` のない子に親の ID を設定する。
+    /// 本文が空の項目は読み飛ばし、診断情報として返す。
+    ///
+    /// ```
+    /// use todo_rs::{MarkdownOptions, TodoList};
+    ///
+    /// let markdown = "# Release\n\n- [ ] (A) Tag release +Web\n  - [x] Update changelog\n";
+    /// let list = TodoList::from_markdown(markdown, &MarkdownOptions::new()).into_list();
+    /// assert_eq!(list.get(0).unwrap().to_string(), "(A) Tag release +Web id:1");
+    /// assert_eq!(list.get(1).unwrap().to_string(), "x Update changelog parent:1");
+    /// ```
+    pub fn from_markdown(content: &str, options: &MarkdownOptions) -> ParseReport {
+        let mut report = ParseReport::default();
+        // (字下げ, 親の位置, タスク)
+        let mut items: Vec<(usize, Option<usize>, Todo)> = Vec::new();
+        let mut open: Vec<(usize, usize)> = Vec::new();
+        let mut fence: Option<&str> = None;
+        let mut offset = 0;
+
+        for (line_num, segment) in content.split_inclusive('\n').enumerate() {
+            let start = offset;
+            offset += segment.len();
+            let line = segment.trim_end_matches('\n').trim_end_matches('\r');
+
+            let trimmed = line.trim_start();
+            if let Some(marker) = fence_marker(trimmed) {
+                match fence {
+                    Some(opening) if marker.starts_with(opening) => fence = None,
+                    Some(_) => {}
+                    None => fence = Some(marker),
+                }
+                continue;
+            }
+            if fence.is_some() {
+                continue;
+            }
+
+            let Some(item) = checklist_item(line) else {
+                continue;
+            };
+
+            let text = if item.checked && !item.text.starts_with("x ") {
+                format!("x {}", item.text)
+            } else {
+                item.text.to_string()
+            };
+            let mut todo = match parse_line(&text) {
+                Ok(todo) => todo,
+                Err(kind) => {
+                    report.diagnostics.push(ParseDiagnostic {
+                        line: line_num + 1,
+                        column: line[..item.text_start].chars().count() + 1,
+                        span: start..start + line.len(),
+                        raw: line.to_string(),
+                        kind,
+                    });
+                    continue;
+                }
+            };
+            if !item.checked && todo.completed {
+                todo.uncomplete();
+            }
+
+            while open
+                .last()
+                .is_some_and(|&(indent, _)| indent >= item.indent)
+            {
+                open.pop();
+            }
+            let parent = match options.nested {
+                NestedItems::Subtasks => open.last().map(|&(_, position)| position),
+                NestedItems::Flatten => None,
+            };
+            open.push((item.indent, items.len()));
+            items.push((item.indent, parent, todo));
+        }
+
+        link_subtasks(&mut items);
+        for (_, _, todo) in items {
+            report.list.add(todo);
+        }
+        report
+    }
+}
+
+/// 項目を字下げして書き出す
+fn write_items(out: &mut String, items: &[(usize, &Todo)]) {
+    for &(depth, todo) in items {
+        let line = todo.to_string();
+        let text = if todo.completed {
+            line.strip_prefix("x ").unwrap_or(&line)
+        } else {
+            &line
+        };
+        let check = if todo.completed { 'x' } else { ' ' };
+        out.push_str(&"  ".repeat(depth));
+        out.push_str(&format!("- [{}] {}\n", check, text));
+    }
+}
+
+/// 子を持つ項目に ID を割り当て、子に `parent:` を設定する
+fn link_subtasks(items: &mut [(usize, Option<usize>, Todo)]) {
+    let mut used: HashSet<String> = items
+        .iter()
+        .filter_map(|(_, _, todo)| todo.persistent_id().map(str::to_string))
+        .collect();
+    let mut next = used
+        .iter()
+        .filter_map(|value| value.parse::<u64>().ok())
+        .max()
+        .map_or(1, |max| max.saturating_add(1));
+
+    for child in 0..items.len() {
+        let Some(parent) = items[child].1 else {
+            continue;
+        };
+        if items[child].2.parent_id().is_some() {
+            continue;
+        }
+        let id = match items[parent].2.persistent_id() {
+            Some(id) => id.to_string(),
+            None => {
+                while used.contains(&next.to_string()) {
+                    next += 1;
+                }
+                let id = next.to_string();
+                used.insert(id.clone());
+                items[parent].2.add_tag(ID_KEY, id.clone());
+                id
+            }
+        };
+        items[child].2.set_parent_id(id);
+    }
+}
+
+/// チェックボックス付きのリスト項目
+struct ChecklistItem<'a> {
+    /// 字下げの幅（タブは 4 文字とみなす）
+    indent: usize,
+    checked: bool,
+    /// 行内での本文の開始位置（バイト）
+    text_start: usize,
+    text: &'a str,
+}
+
+fn checklist_item(line: &str) -> Option<ChecklistItem<'_>> {
+    let body = line.trim_start_matches([' ', '\t']);
+    let indent = line[..line.len() - body.len()]
+        .chars()
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum();
+
+    // リストのマーカー (`-`, `*`, `+`, `1.`, `1)`)
+    let rest = match body.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = body.len() - body.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if digits == 0 {
+                return None;
+            }
+            body[digits..].strip_prefix(['.', ')'])?
+        }
+    };
+    let rest = rest
+        .strip_prefix([' ', '\t'])?
+        .trim_start_matches([' ', '\t']);
+
+    let checked = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let after = &rest[3..];
+    if !after.is_empty() && !after.starts_with([' ', '\t']) {
+        return None;
+    }
+    let text = after.trim();
+    let text_start = if text.is_empty() {
+        line.len()
+    } else {
+        line.len() - after.trim_start().len()
+    };
+
+    Some(ChecklistItem {
+        indent,
+        checked,
+        text_start,
+        text,
+    })
+}
+
+/// コードブロックの区切り (```` ``` ```` / `~~~`) ならその記号列を返す
+fn fence_marker(line: &str) -> Option<&str> {
+    ["```", "~~~"].into_iter().find_map(|mark| {
+        let c = mark.chars().next()?;
+        line.starts_with(mark)
+            .then(|| &line[..line.len() - line.trim_start_matches(c).len()])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(list: &TodoList) -> Vec<String> {
+        list.iter().map(Todo::to_string).collect()
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let list = TodoList::from_string(
+            "(A) 2024-03-01 Write spec due:2024-03-08\nx 2024-03-02 2024-03-01 Fix typo",
+        )
+        .unwrap();
+        assert_eq!(
+            list.to_markdown(&MarkdownOptions::new()),
+            "- [ ] (A) 2024-03-01 Write spec due:2024-03-08\n- [x] 2024-03-02 2024-03-01 Fix typo\n"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_grouped_by_context() {
+        let list = TodoList::from_string(
+            "Call mom @phone @home\nBuy milk\nVacuum @home\nCall bank @phone",
+        )
+        .unwrap();
+        let options = MarkdownOptions::new()
+            .with_group_by(GroupBy::Context)
+            .with_heading_level(3);
+        assert_eq!(
+            list.to_markdown(&options),
+            "### @home\n\n- [ ] Vacuum @home\n\n\
+             ### @phone\n\n- [ ] Call mom @phone @home\n- [ ] Call bank @phone\n\n\
+             ### (なし)\n\n- [ ] Buy milk\n"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_nested() {
+        let list = TodoList::from_string(
+            "Release +Web id:1\nOther +Ops\nChangelog parent:1 id:2\nTypos parent:2",
+        )
+        .unwrap();
+        let grouped = MarkdownOptions::new().with_group_by(GroupBy::Project);
+        assert_eq!(
+            list.to_markdown(&grouped),
+            "## +Ops\n\n- [ ] Other +Ops\n\n\
+             ## +Web\n\n- [ ] Release +Web id:1\n  - [ ] Changelog parent:1 id:2\n    - [ ] Typos parent:2\n"
+        );
+
+        let flat = MarkdownOptions::new().with_nested(NestedItems::Flatten);
+        assert!(
+            list.to_markdown(&flat)
+                .lines()
+                .all(|line| line.starts_with("- [ ]"))
+        );
+    }
+
+    #[test]
+    fn test_from_markdown() {
+        let markdown = "\
+# Sprint
+
+Some notes with a [link](https://example.com).
+
+- [ ] (B) 2024-03-01 Write spec +Docs due:2024-03-08
+* [X] Fix typo @desk
+1. [ ] Numbered item
+- plain item
+-[ ] not an item
+- [x] 2024-03-05 2024-03-01 Ship it
+";
+        let report = TodoList::from_markdown(markdown, &MarkdownOptions::new());
+        assert!(report.is_clean());
+        let list = report.into_list();
+        assert_eq!(
+            lines(&list),
+            [
+                "(B) 2024-03-01 Write spec +Docs due:2024-03-08",
+                "x Fix typo @desk",
+                "Numbered item",
+                "x 2024-03-05 2024-03-01 Ship it",
+            ]
+        );
+        assert_eq!(
+            list.get(0)
+                .unwrap()
+                .due_date()
+                .unwrap()
+                .unwrap()
+                .to_string(),
+            "2024-03-08"
+        );
+        assert!(list.get(1).unwrap().has_context("desk"));
+    }
+
+    #[test]
+    fn test_from_markdown_checkbox_wins() {
+        let list =
+            TodoList::from_markdown("- [ ] x 2024-03-05 Reopened\n", &MarkdownOptions::new())
+                .into_list();
+        let todo = list.get(0).unwrap();
+        assert!(!todo.completed);
+        assert!(todo.completion_date.is_none());
+    }
+
+    #[test]
+    fn test_from_markdown_nested() {
+        let markdown = "\
+- [ ] Release id:3
+  - [ ] Changelog
+    - [x] Typos
+\t- [ ] Tag
+- [ ] Other
+";
+        let list = TodoList::from_markdown(markdown, &MarkdownOptions::new()).into_list();
+        assert_eq!(
+            lines(&list),
+            [
+                "Release id:3",
+                "Changelog id:4 parent:3",
+                "x Typos parent:4",
+                "Tag parent:4",
+                "Other",
+            ]
+        );
+        assert_eq!(list.children(1), vec![2, 3]);
+
+        let flat = MarkdownOptions::new().with_nested(NestedItems::Flatten);
+        let list = TodoList::from_markdown(markdown, &flat).into_list();
+        assert!(list.iter().all(|todo| todo.parent_id().is_none()));
+        assert_eq!(list.get(0).unwrap().to_string(), "Release id:3");
+    }
+
+    #[test]
+    fn test_from_markdown_skips_code_and_reports_empty_items() {
+        let markdown = "\
+```markdown
+- [ ] example only
+```
+- [ ]
+- [x] Real task
+";
+        let report = TodoList::from_markdown(markdown, &MarkdownOptions::new());
+        assert_eq!(lines(&report.list), ["x Real task"]);
+        assert_eq!(report.diagnostics.len(), 1);
+        assert_eq!(report.diagnostics[0].line, 4);
+        assert_eq!(report.diagnostics[0].column, 6);
+    }
+
+    #[test]
+    fn test_markdown_round_trip() {
+        let content =
+            "(A) Plan +Web id:1\nx 2024-03-02 Draft parent:1\nBuy milk @store\nReview +Web";
+        let list = TodoList::from_string(content).unwrap();
+        let options = MarkdownOptions::new().with_group_by(GroupBy::Project);
+        let restored = TodoList::from_markdown(&list.to_markdown(&options), &options).into_list();
+
+        let mut expected = lines(&list);
+        expected.sort();
+        let mut actual = lines(&restored);
+        actual.sort();
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,213 @@
+use std::fmt;
+
+/// これ以上の組み合わせ数になる場合は、差分の中間部分を 1 つの変更として扱う
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// 3-way マージで解決できなかった競合
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// ディスク上のファイルでの開始行番号 (1 始まり)
+    pub line: usize,
+
+    /// 読み込み時の行
+    pub base: Vec<String>,
+
+    /// メモリ上のリストの行
+    pub ours: Vec<String>,
+
+    /// ディスク上のファイルの行
+    pub theirs: Vec<String>,
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}行目: 読み込み時 {}行 / 変更後 {}行 / ディスク上 {}行",
+            self.line,
+            self.base.len(),
+            self.ours.len(),
+            self.theirs.len()
+        )
+    }
+}
+
+/// 行単位の 3-way マージ
+///
+/// 両方で変更された箇所が一致しない場合は競合として返す。
+pub(crate) fn merge_lines(
+    base: &[&str],
+    ours: &[&str],
+    theirs: &[&str],
+) -> Result<Vec<String>, Vec<MergeConflict>> {
+    let to_ours = matching_lines(base, ours);
+    let to_theirs = matching_lines(base, theirs);
+
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+    let (mut i, mut j, mut k) = (0, 0, 0);
+
+    loop {
+        // 3 つすべてで一致する次の行を探す
+        let stable = (i..base.len()).find_map(|b| match (to_ours[b], to_theirs[b]) {
+            (Some(o), Some(t)) if o >= j && t >= k => Some((b, o, t)),
+            _ => None,
+        });
+        let (b, o, t) = stable.unwrap_or((base.len(), ours.len(), theirs.len()));
+
+        if (b, o, t) != (i, j, k) {
+            let chunk = (&base[i..b], &ours[j..o], &theirs[k..t]);
+            match resolve(chunk.0, chunk.1, chunk.2) {
+                Some(lines) => merged.extend(lines.iter().map(|line| line.to_string())),
+                None => conflicts.push(MergeConflict {
+                    line: k + 1,
+                    base: chunk.0.iter().map(|line| line.to_string()).collect(),
+                    ours: chunk.1.iter().map(|line| line.to_string()).collect(),
+                    theirs: chunk.2.iter().map(|line| line.to_string()).collect(),
+                }),
+            }
+        }
+
+        if stable.is_none() {
+            break;
+        }
+
+        merged.push(base[b].to_string());
+        (i, j, k) = (b + 1, o + 1, t + 1);
+    }
+
+    if conflicts.is_empty() {
+        Ok(merged)
+    } else {
+        Err(conflicts)
+    }
+}
+
+/// 変更箇所を解決する（片方だけが変更した場合か、同じ変更の場合）
+fn resolve<'a>(base: &[&str], ours: &'a [&'a str], theirs: &'a [&'a str]) -> Option<&'a [&'a str]> {
+    if ours == base {
+        Some(theirs)
+    } else if theirs == base || ours == theirs {
+        Some(ours)
+    } else {
+        None
+    }
+}
+
+/// `a` の各行に対応する `b` の行（最長共通部分列）を求める
+fn matching_lines(a: &[&str], b: &[&str]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; a.len()];
+
+    // 先頭と末尾の共通部分
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    for (i, m) in matches.iter_mut().enumerate().take(prefix) {
+        *m = Some(i);
+    }
+    for n in 0..suffix {
+        matches[a.len() - 1 - n] = Some(b.len() - 1 - n);
+    }
+
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+    if a_mid.is_empty() || b_mid.is_empty() || a_mid.len() * b_mid.len() > MAX_LCS_CELLS {
+        return matches;
+    }
+
+    // lengths[i][j] = a_mid[i..] と b_mid[j..] の最長共通部分列の長さ
+    let width = b_mid.len() + 1;
+    let mut lengths = vec![0u32; (a_mid.len() + 1) * width];
+    for i in (0..a_mid.len()).rev() {
+        for j in (0..b_mid.len()).rev() {
+            lengths[i * width + j] = if a_mid[i] == b_mid[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < a_mid.len() && j < b_mid.len() {
+        if a_mid[i] == b_mid[j] {
+            matches[prefix + i] = Some(prefix + j);
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_independent_changes() {
+        let base = ["a", "b", "c", "d"];
+        let ours = ["a", "B", "c", "d", "e"];
+        let theirs = ["a", "b", "c", "x", "d"];
+        assert_eq!(
+            merge_lines(&base, &ours, &theirs).unwrap(),
+            ["a", "B", "c", "x", "d", "e"]
+        );
+    }
+
+    #[test]
+    fn test_merge_same_change() {
+        let base = ["a", "b"];
+        let ours = ["a", "c"];
+        assert_eq!(merge_lines(&base, &ours, &ours).unwrap(), ["a", "c"]);
+    }
+
+    #[test]
+    fn test_merge_deletions() {
+        let base = ["a", "b", "c", "d", "e"];
+        let ours = ["a", "c", "d", "e"];
+        let theirs = ["a", "b", "c", "e"];
+        assert_eq!(merge_lines(&base, &ours, &theirs).unwrap(), ["a", "c", "e"]);
+
+        // 隣接する変更は競合として扱う
+        let base = ["a", "b", "c"];
+        assert!(merge_lines(&base, &["a", "c"], &["a", "b"]).is_err());
+    }
+
+    #[test]
+    fn test_merge_conflict() {
+        let base = ["a", "b", "c"];
+        let ours = ["a", "B1", "c"];
+        let theirs = ["z", "a", "B2", "c"];
+        let conflicts = merge_lines(&base, &ours, &theirs).unwrap_err();
+
+        assert_eq!(
+            conflicts,
+            [MergeConflict {
+                line: 3,
+                base: vec!["b".into()],
+                ours: vec!["B1".into()],
+                theirs: vec!["B2".into()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_matching_lines() {
+        let a = ["x", "a", "b", "c", "y"];
+        let b = ["x", "b", "q", "c", "y"];
+        assert_eq!(
+            matching_lines(&a, &b),
+            [Some(0), None, Some(1), Some(3), Some(4)]
+        );
+    }
+}
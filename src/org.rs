@@ -0,0 +1,447 @@
+use crate::{Priority, Result, Todo, TodoError, TodoList};
+use chrono::NaiveDate;
+
+/// 作成日を保持するプロパティ
+const CREATED_PROPERTY: &str = "CREATED";
+
+/// 見出しのタグと区別するため、説明文末尾の `:word:` に付けるゼロ幅スペース
+const TAG_ESCAPE: char = '\u{200B}';
+
+impl Todo {
+    /// Org の見出しとして書き出す
+    fn write_org(&self, out: &mut String) {
+        let mut headline = String::from("* ");
+        headline.push_str(if self.completed { "DONE" } else { "TODO" });
+        if let Some(priority) = self.priority {
+            headline.push_str(&format!(" [#{}]", priority.as_char()));
+        }
+        if !self.description.is_empty() {
+            headline.push(' ');
+            headline.push_str(&self.description);
+            // 後ろに何も続かない場合、末尾の `:word:` が見出しのタグとして読まれてしまう
+            if self.projects.is_empty()
+                && self.contexts.is_empty()
+                && self
+                    .description
+                    .split(' ')
+                    .next_back()
+                    .is_some_and(is_tag_group)
+            {
+                headline.push(TAG_ESCAPE);
+            }
+        }
+        for project in &self.projects {
+            headline.push_str(&format!(" +{}", project));
+        }
+        if !self.contexts.is_empty() {
+            headline.push_str(&format!(" :{}:", self.contexts.join(":")));
+        }
+        out.push_str(&headline);
+        out.push('\n');
+
+        let (due, threshold) = self.export_dates();
+        let planning: Vec<String> = [
+            (
+                "CLOSED",
+                self.completion_date.filter(|_| self.completed),
+                '[',
+            ),
+            ("DEADLINE", due, '<'),
+            ("SCHEDULED", threshold, '<'),
+        ]
+        .into_iter()
+        .filter_map(|(keyword, date, open)| {
+            date.map(|date| format!("{}: {}", keyword, timestamp(date, open)))
+        })
+        .collect();
+        if !planning.is_empty() {
+            out.push_str(&format!("  {}\n", planning.join(" ")));
+        }
+
+        let mut properties: Vec<(String, String)> = self
+            .export_tags()
+            .into_iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        if let Some(created) = self.creation_date {
+            properties.insert(0, (CREATED_PROPERTY.to_string(), timestamp(created, '[')));
+        }
+        if !properties.is_empty() {
+            out.push_str("  :PROPERTIES:\n");
+            for (key, value) in properties {
+                out.push_str(&format!("  :{}: {}\n", key, value));
+            }
+            out.push_str("  :END:\n");
+        }
+    }
+}
+
+impl TodoList {
+    /// Org-mode の見出しとして出力
+    ///
+    /// 完了状態は `TODO` / `DONE`、優先度は `[#A]`、コンテキストは見出しのタグ (`:phone:`) になる。
+    /// プロジェクトは見出しの中に `+Project` の形で残す。
+    /// 説明文が `:smile:` のような語で終わる場合は、タグと区別するためゼロ幅スペースを付ける。
+    /// 完了日は `CLOSED:`、`due:` は `DEADLINE:`、`t:` は `SCHEDULED:` に対応し、
+    /// 作成日は `CREATED` プロパティ、その他のタグはキーの大文字・小文字をそのまま保ったプロパティになる。
+    ///
+    /// ```
+    /// use todo_rs::TodoList;
+    ///
+    /// let list = TodoList::from_string("(A) Call mom +Family @phone due:2024-03-08").unwrap();
+    /// assert_eq!(
+    ///     list.to_org(),
+    ///     "* TODO [#A] Call mom +Family :phone:\n  DEADLINE: <2024-03-08 Fri>\n"
+    /// );
+    /// ```
+    pub fn to_org(&self) -> String {
+        let mut out = String::new();
+        for todo in self.iter() {
+            todo.write_org(&mut out);
+        }
+        out
+    }
+
+    /// Org-mode の見出しからタスクを読み込む
+    ///
+    /// `TODO` または `DONE` のキーワードを持つ見出しだけをタスクとして読み、
+    /// 見出しの深さは区別しない。キーワードのない見出しや本文は無視する。
+    /// 見出しの `+Project` はプロジェクト、タグはコンテキストになる（`@home` の `@` は除く）。
+    /// 大文字の `CREATED` 以外のプロパティは、キーの大文字・小文字をそのまま保ったタグとして保持する
+    /// （値の空白は `-` に置き換える）。
+    /// 読み込めない場合は行番号を持つ [`TodoError::Import`] を返す。
+    pub fn from_org(content: &str) -> Result<TodoList> {
+        let mut list = TodoList::new();
+        let mut current: Option<Todo> = None;
+        let mut drawer: Option<String> = None;
+
+        for (i, line) in content.lines().enumerate() {
+            let error = |message: String| org_error(i + 1, message);
+            let line = line.trim_end_matches('\r');
+
+            if let Some(rest) = headline(line) {
+                if let Some(drawer) = drawer.take() {
+                    return Err(error(format!(":{}: ドロワーが閉じていません", drawer)));
+                }
+                if let Some(mut todo) = current.take() {
+                    todo.normalize_completion();
+                    list.add(todo);
+                }
+                current = parse_headline(rest).map_err(error)?;
+                continue;
+            }
+
+            let Some(todo) = current.as_mut() else {
+                continue;
+            };
+            let trimmed = line.trim();
+
+            if let Some(name) = &drawer {
+                if trimmed.eq_ignore_ascii_case(":END:") {
+                    drawer = None;
+                } else if name == "PROPERTIES" && !trimmed.is_empty() {
+                    apply_property(todo, trimmed).map_err(error)?;
+                }
+            } else if let Some(name) = drawer_name(trimmed) {
+                drawer = Some(name.to_uppercase());
+            } else {
+                apply_planning(todo, trimmed).map_err(error)?;
+            }
+        }
+
+        if let Some(drawer) = drawer {
+            return Err(org_error(
+                content.lines().count(),
+                format!(":{}: ドロワーが閉じていません", drawer),
+            ));
+        }
+        if let Some(mut todo) = current {
+            todo.normalize_completion();
+            list.add(todo);
+        }
+        Ok(list)
+    }
+}
+
+/// 見出しの行ならアスタリスクを除いた残りを返す
+fn headline(line: &str) -> Option<&str> {
+    let rest = line.trim_start_matches('*');
+    if rest.len() == line.len() {
+        return None;
+    }
+    rest.strip_prefix(' ')
+}
+
+/// 見出しを読む（`TODO` / `DONE` のない見出しは `None`）
+fn parse_headline(rest: &str) -> std::result::Result<Option<Todo>, String> {
+    let mut words: Vec<&str> = rest.split_whitespace().collect();
+    let completed = match words.first() {
+        Some(&"TODO") => false,
+        Some(&"DONE") => true,
+        _ => return Ok(None),
+    };
+    words.remove(0);
+
+    let mut todo = Todo::new("");
+    todo.completed = completed;
+
+    if let Some(cookie) = words
+        .first()
+        .and_then(|word| word.strip_prefix("[#"))
+        .and_then(|word| word.strip_suffix(']'))
+    {
+        let priority = cookie
+            .chars()
+            .next()
+            .filter(|_| cookie.chars().count() == 1)
+            .and_then(Priority::new)
+            .ok_or_else(|| format!("優先度として読めません: [#{}]", cookie))?;
+        todo.priority = Some(priority);
+        words.remove(0);
+    }
+
+    // 末尾の `:tag1:tag2:` は見出しのタグ（Org の `@home` はコンテキストの `home`）
+    if let Some(tags) = words.last().copied().filter(|word| is_tag_group(word)) {
+        for tag in tags.split(':') {
+            let tag = tag.strip_prefix('@').unwrap_or(tag);
+            if !tag.is_empty() && !todo.has_context(tag) {
+                todo.add_context(tag);
+            }
+        }
+        words.pop();
+    }
+
+    let mut description = Vec::new();
+    for word in words {
+        match word.strip_prefix('+') {
+            Some(project) if !project.is_empty() => {
+                if !todo.has_project(project) {
+                    todo.add_project(project);
+                }
+            }
+            _ => description.push(word),
+        }
+    }
+    if let Some(last) = description.last_mut()
+        && let Some(word) = last.strip_suffix(TAG_ESCAPE)
+        && is_tag_group(word)
+    {
+        *last = word;
+    }
+    todo.description = description.join(" ");
+
+    if todo.description.is_empty() && todo.projects.is_empty() && todo.contexts.is_empty() {
+        return Err("見出しが空です".to_string());
+    }
+    Ok(Some(todo))
+}
+
+/// 見出しのタグ `:tag1:tag2:` の形かチェック
+fn is_tag_group(word: &str) -> bool {
+    word.len() > 2 && word.starts_with(':') && word.ends_with(':')
+}
+
+/// `CLOSED:` / `DEADLINE:` / `SCHEDULED:` の行を反映する（それ以外の行は無視する）
+fn apply_planning(todo: &mut Todo, line: &str) -> std::result::Result<(), String> {
+    let keywords = ["CLOSED:", "DEADLINE:", "SCHEDULED:"];
+    if !keywords.iter().any(|keyword| line.starts_with(keyword)) {
+        return Ok(());
+    }
+
+    for keyword in keywords {
+        let Some(position) = line.find(keyword) else {
+            continue;
+        };
+        let value = line[position + keyword.len()..].trim_start();
+        let date =
+            parse_timestamp(value).ok_or_else(|| format!("日時として読めません: {}", value))?;
+        match keyword {
+            "CLOSED:" => todo.completion_date = Some(date),
+            "DEADLINE:" => todo.set_due_date(date),
+            _ => todo.set_threshold_date(date),
+        }
+    }
+    Ok(())
+}
+
+/// プロパティドロワーの `:KEY: value` の行を反映する
+fn apply_property(todo: &mut Todo, line: &str) -> std::result::Result<(), String> {
+    let invalid = || format!("プロパティとして読めません: {}", line);
+    let (key, value) = line
+        .strip_prefix(':')
+        .and_then(|rest| rest.split_once(':'))
+        .ok_or_else(invalid)?;
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return Err(invalid());
+    }
+
+    // 追記用の `KEY+` は区別しない
+    let key = key.strip_suffix('+').unwrap_or(key);
+    // 書き出し時にタグのキーの大文字・小文字を保つため、`created:` のタグと区別する
+    if key == CREATED_PROPERTY {
+        let date = parse_timestamp(value.trim())
+            .ok_or_else(|| format!("日時として読めません: {}", value.trim()))?;
+        todo.creation_date = Some(date);
+        return Ok(());
+    }
+
+    let value = value.split_whitespace().collect::<Vec<_>>().join("-");
+    if !value.is_empty() {
+        todo.add_tag(key, value);
+    }
+    Ok(())
+}
+
+/// `:NAME:` だけの行ならドロワーの名前を返す
+fn drawer_name(line: &str) -> Option<&str> {
+    let name = line.strip_prefix(':')?.strip_suffix(':')?;
+    (!name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-'))
+    .then_some(name)
+}
+
+/// `<2024-03-08 Fri>` や `[2024-03-08 Fri 10:00]` の日付部分を読む
+fn parse_timestamp(value: &str) -> Option<NaiveDate> {
+    let date = value.strip_prefix(['<', '['])?.get(..10)?;
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// `<` ならアクティブ、`[` なら非アクティブなタイムスタンプを作る
+fn timestamp(date: NaiveDate, open: char) -> String {
+    let close = if open == '<' { '>' } else { ']' };
+    format!("{}{}{}", open, date.format("%Y-%m-%d %a"), close)
+}
+
+fn org_error(line: usize, message: String) -> TodoError {
+    TodoError::Import {
+        format: "Org",
+        line,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_same_todos;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_to_org() {
+        let list = TodoList::from_string(
+            "x 2024-03-02 2024-02-20 Write spec +Docs @desk @office t:2024-02-25 est:3\n\
+             (B) Call bank due:someday",
+        )
+        .unwrap();
+        assert_eq!(
+            list.to_org(),
+            "* DONE Write spec +Docs :desk:office:\n\
+             \x20 CLOSED: [2024-03-02 Sat] SCHEDULED: <2024-02-25 Sun>\n\
+             \x20 :PROPERTIES:\n\
+             \x20 :CREATED: [2024-02-20 Tue]\n\
+             \x20 :est: 3\n\
+             \x20 :END:\n\
+             * TODO [#B] Call bank\n\
+             \x20 :PROPERTIES:\n\
+             \x20 :due: someday\n\
+             \x20 :END:\n"
+        );
+    }
+
+    #[test]
+    fn test_from_org() {
+        let content = "\
+#+TITLE: Tasks
+* Inbox
+Notes that are not tasks.
+** TODO [#A] Call mom +Family :phone:@home:
+   DEADLINE: <2024-03-08 Fri 10:00> SCHEDULED: <2024-03-01 Fri>
+   :PROPERTIES:
+   :CREATED:  [2024-02-20 Tue 09:12]
+   :Effort:   1:30
+   :CATEGORY: Personal stuff
+   :END:
+   Remember the birthday.
+** DONE Renew passport
+   CLOSED: [2024-02-28 Wed 18:00]
+   :LOGBOOK:
+   - State \"DONE\" from \"TODO\" [2024-02-28 Wed 18:00]
+   :END:
+";
+        let list = TodoList::from_org(content).unwrap();
+        assert_eq!(list.len(), 2);
+
+        let call = list.get(0).unwrap();
+        assert!(!call.completed);
+        assert_eq!(call.priority, Priority::new('A'));
+        assert_eq!(call.description, "Call mom");
+        assert!(call.has_project("Family"));
+        assert_eq!(call.contexts, ["phone", "home"]);
+        assert_eq!(call.due_date().unwrap(), Some(date(2024, 3, 8)));
+        assert_eq!(call.threshold_date().unwrap(), Some(date(2024, 3, 1)));
+        assert_eq!(call.creation_date, Some(date(2024, 2, 20)));
+        assert_eq!(call.get_tag("Effort").unwrap(), "1:30");
+        assert_eq!(call.get_tag("CATEGORY").unwrap(), "Personal-stuff");
+
+        let passport = list.get(1).unwrap();
+        assert!(passport.completed);
+        assert_eq!(passport.completion_date, Some(date(2024, 2, 28)));
+        assert!(passport.tags.is_empty());
+    }
+
+    #[test]
+    fn test_from_org_closed_on_open_task_is_ignored() {
+        let list = TodoList::from_org("* TODO Reopened\n  CLOSED: [2024-02-28 Wed]\n").unwrap();
+        let todo = list.get(0).unwrap();
+        assert!(!todo.completed);
+        assert!(todo.completion_date.is_none());
+    }
+
+    #[test]
+    fn test_org_round_trip() {
+        let content = "\
+x 2024-03-02 2024-02-20 Write spec +Docs @desk t:2024-02-25 id:7
+(A) 2024-02-21 Call mom +Family @phone due:2024-03-08 rec:+1w
+(Z) Someday @home
+Draft email parent:7 due:later ownerID:bob
+Read notes created:yesterday Created:2024-01-01
+Wave hi :smile:";
+        let list = TodoList::from_string(content).unwrap();
+        let restored = TodoList::from_org(&list.to_org()).unwrap();
+
+        assert_same_todos(&restored, &list);
+    }
+
+    #[test]
+    fn test_from_org_errors() {
+        let cases = [
+            ("* TODO [#AA] Bad priority\n", 1),
+            ("* TODO Task\n  DEADLINE: <tomorrow>\n", 2),
+            ("* TODO\n", 1),
+            (
+                "* TODO Task\n  :PROPERTIES:\n  :CREATED: soon\n  :END:\n",
+                3,
+            ),
+            ("* TODO Task\n  :PROPERTIES:\n  :EST: 2\n", 3),
+            ("* TODO Task\n  :PROPERTIES:\n* TODO Next\n", 3),
+        ];
+        for (content, expected) in cases {
+            match TodoList::from_org(content) {
+                Err(TodoError::Import {
+                    format: "Org",
+                    line,
+                    ..
+                }) => assert_eq!(line, expected, "{content:?}"),
+                other => panic!("{content:?}: {other:?}"),
+            }
+        }
+
+        let message = TodoList::from_org("* TODO\n").unwrap_err().to_string();
+        assert!(message.starts_with("Org の1行目"), "{message}");
+    }
+}
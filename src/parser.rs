@@ -1,17 +1,70 @@
+use crate::diagnostic::ParseErrorKind;
 use crate::{Priority, Result, Todo, TodoError};
 use chrono::NaiveDate;
+use std::iter::Peekable;
 
 /// Todo.txt 形式の文字列をパースする
 pub fn parse_todo(line: &str) -> Result<Todo> {
+    parse_line(line).map_err(|kind| TodoError::ParseError(kind.to_string()))
+}
+
+/// 1 行をパースし、失敗した場合はその理由を返す
+pub(crate) fn parse_line(line: &str) -> std::result::Result<Todo, ParseErrorKind> {
     let line = line.trim();
 
     if line.is_empty() {
-        return Err(TodoError::ParseError("空の行はパースできません".into()));
+        return Err(ParseErrorKind::EmptyLine);
     }
 
     let mut todo = Todo::new("");
     let mut parts = line.split_whitespace().peekable();
+    parse_header(&mut todo, &mut parts);
+
+    // 残りの部分（説明、コンテキスト、プロジェクト、タグ）をパース
+    let mut description_parts = Vec::new();
+
+    for part in parts {
+        match classify(part) {
+            Token::Context(context) => todo.contexts.push(context.to_string()),
+            Token::Project(project) => todo.projects.push(project.to_string()),
+            Token::Tag(key, value) => {
+                todo.tags.insert(key.to_string(), value.to_string());
+            }
+            Token::Word(word) => description_parts.push(word),
+        }
+    }
+
+    todo.description = description_parts.join(" ");
+
+    if todo.description.is_empty() && todo.contexts.is_empty() && todo.projects.is_empty() {
+        return Err(ParseErrorKind::EmptyContent);
+    }
+
+    todo.raw = Some(line.to_string());
+
+    Ok(todo)
+}
+
+/// 本文中のトークンの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Token<'a> {
+    /// 説明文の単語
+    Word(&'a str),
+    /// コンテキスト (@ を除いた名前)
+    Context(&'a str),
+    /// プロジェクト (+ を除いた名前)
+    Project(&'a str),
+    /// key:value タグ
+    Tag(&'a str, &'a str),
+}
 
+/// 完了マーカー・日付・優先度を読み進めて `todo` に設定する
+///
+/// 呼び出し後の `parts` は本文の最初のトークンを指す。
+pub(crate) fn parse_header<'a, I>(todo: &mut Todo, parts: &mut Peekable<I>)
+where
+    I: Iterator<Item = &'a str>,
+{
     // 完了チェック
     if parts.peek() == Some(&"x") {
         todo.completed = true;
@@ -45,47 +98,27 @@ pub fn parse_todo(line: &str) -> Result<Todo> {
         todo.creation_date = Some(date);
         parts.next();
     }
+}
 
-    // 残りの部分（説明、コンテキスト、プロジェクト、タグ）をパース
-    let mut description_parts = Vec::new();
-
-    for part in parts {
-        if part.starts_with('@') && part.len() > 1 {
-            // コンテキスト
-            todo.contexts.push(part[1..].to_string());
-        } else if part.starts_with('+') && part.len() > 1 {
-            // プロジェクト
-            todo.projects.push(part[1..].to_string());
-        } else if part.contains(':') {
-            // key:value タグ
-            if let Some((key, value)) = part.split_once(':')
-                && !key.is_empty()
-                && !value.is_empty()
-                && !key.contains(char::is_whitespace)
-                && !value.contains(char::is_whitespace)
-            {
-                todo.tags.insert(key.to_string(), value.to_string());
-                continue;
-            }
-            // タグとして解釈できない場合は説明の一部として扱う
-            description_parts.push(part);
-        } else {
-            // 通常のテキスト
-            description_parts.push(part);
-        }
-    }
-
-    todo.description = description_parts.join(" ");
-
-    if todo.description.is_empty() && todo.contexts.is_empty() && todo.projects.is_empty() {
-        return Err(TodoError::ParseError("タスクの内容が空です".into()));
+/// 本文中の 1 トークンを分類する
+pub(crate) fn classify(part: &str) -> Token<'_> {
+    if part.starts_with('@') && part.len() > 1 {
+        Token::Context(&part[1..])
+    } else if part.starts_with('+') && part.len() > 1 {
+        Token::Project(&part[1..])
+    } else if let Some((key, value)) = part.split_once(':')
+        && !key.is_empty()
+        && !value.is_empty()
+    {
+        Token::Tag(key, value)
+    } else {
+        // タグとして解釈できない場合は説明の一部として扱う
+        Token::Word(part)
     }
-
-    Ok(todo)
 }
 
 /// YYYY-MM-DD 形式の日付をパースする
-fn parse_date(s: &str) -> Result<NaiveDate> {
+pub(crate) fn parse_date(s: &str) -> Result<NaiveDate> {
     NaiveDate::parse_from_str(s, "%Y-%m-%d")
         .map_err(|_| TodoError::InvalidDateFormat(s.to_string()))
 }
@@ -169,9 +202,33 @@ mod tests {
         assert_eq!(todo.contexts.len(), 0);
     }
 
+    #[test]
+    fn test_parse_keeps_raw_line() {
+        let todo = parse_todo("  Call +Mom about @phone bill  ").unwrap();
+        assert_eq!(todo.raw.as_deref(), Some("Call +Mom about @phone bill"));
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("@phone"), Token::Context("phone"));
+        assert_eq!(classify("+Mom"), Token::Project("Mom"));
+        assert_eq!(classify("due:2024-11-10"), Token::Tag("due", "2024-11-10"));
+        assert_eq!(classify("@"), Token::Word("@"));
+        assert_eq!(classify("note:"), Token::Word("note:"));
+    }
+
     #[test]
     fn test_parse_empty_line() {
         assert!(parse_todo("").is_err());
         assert!(parse_todo("   ").is_err());
     }
+
+    #[test]
+    fn test_parse_line_error_kind() {
+        assert_eq!(parse_line("  ").unwrap_err(), ParseErrorKind::EmptyLine);
+        assert_eq!(
+            parse_line("x 2024-11-03 due:2024-11-10").unwrap_err(),
+            ParseErrorKind::EmptyContent
+        );
+    }
 }
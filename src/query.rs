@@ -0,0 +1,614 @@
+use crate::parser::parse_date;
+use crate::{Priority, Recurrence, RecurrenceUnit, Result, Todo, TodoError, TodoList};
+use chrono::{Days, Months, NaiveDate};
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// 文字列から組み立てるタスクの絞り込み条件
+///
+/// 空白で区切った条件はすべて満たす必要があり (`and` は省略可能)、
+/// `or`・`not`・括弧で組み合わせられる。
+///
+/// | 書式 | 意味 |
+/// |------|------|
+/// | `+Work` / `@office` | プロジェクト・コンテキストを持つ |
+/// | `done` | 完了済み |
+/// | `pri:A` / `pri:A..C` | 優先度が一致する・範囲内にある |
+/// | `key<value` など | タグの値の比較 (`=` `:` `!=` `<` `<=` `>` `>=`) |
+/// | `report` / `"weekly report"` | 説明文に含む（大文字小文字を区別しない） |
+///
+/// 比較する値は `2024-01-31` や `today`・`today+7d`・`today-1w` なら日付、
+/// 数値として読めれば数値、それ以外は文字列として扱う。
+/// `created` と `completed` はそれぞれ作成日・完了日との比較になる。
+/// タグがない、または値を同じ型として読めない場合、比較は常に偽になる。
+///
+/// ```
+/// use todo_rs::TodoList;
+///
+/// let list = TodoList::from_string("(A) Report +Work due:2024-01-10\n(D) Report +Work").unwrap();
+/// let found = list.query("+Work pri:A..C not done \"report\"").unwrap();
+/// assert_eq!(found.len(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    /// 両方を満たす
+    And(Box<Query>, Box<Query>),
+    /// どちらかを満たす
+    Or(Box<Query>, Box<Query>),
+    /// 満たさない
+    Not(Box<Query>),
+    /// プロジェクトを持つ
+    Project(String),
+    /// コンテキストを持つ
+    Context(String),
+    /// 完了済み
+    Completed,
+    /// 優先度が範囲内にある（両端を含む）
+    Priority(Priority, Priority),
+    /// 説明文に含む
+    Text(String),
+    /// タグなどの値の比較
+    Compare {
+        /// 比較するキー
+        key: String,
+        /// 比較演算子
+        op: CompareOp,
+        /// 比較する値
+        value: QueryValue,
+    },
+}
+
+/// 比較演算子
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    /// `=` または `:`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+}
+
+impl CompareOp {
+    fn matches(self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Eq => ordering == Ordering::Equal,
+            CompareOp::Ne => ordering != Ordering::Equal,
+            CompareOp::Lt => ordering == Ordering::Less,
+            CompareOp::Le => ordering != Ordering::Greater,
+            CompareOp::Gt => ordering == Ordering::Greater,
+            CompareOp::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+/// 比較する値
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryValue {
+    /// 日付
+    Date(DateValue),
+    /// 数値
+    Number(f64),
+    /// 文字列
+    Text(String),
+}
+
+/// 日付の値（相対指定は評価時の今日を基準にする）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateValue {
+    /// 固定の日付
+    Fixed(NaiveDate),
+    /// 今日
+    Today,
+    /// 今日から指定した期間後
+    After(Recurrence),
+    /// 今日から指定した期間前
+    Before(Recurrence),
+}
+
+impl DateValue {
+    /// 今日を基準に日付を求める
+    pub fn resolve(&self, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            DateValue::Fixed(date) => Some(*date),
+            DateValue::Today => Some(today),
+            DateValue::After(step) => step.advance(today),
+            DateValue::Before(step) => match step.unit {
+                RecurrenceUnit::Day => today.checked_sub_days(Days::new(step.amount.into())),
+                RecurrenceUnit::Week => {
+                    today.checked_sub_days(Days::new(u64::from(step.amount) * 7))
+                }
+                RecurrenceUnit::Month => today.checked_sub_months(Months::new(step.amount)),
+                RecurrenceUnit::Year => {
+                    today.checked_sub_months(Months::new(step.amount.checked_mul(12)?))
+                }
+            },
+        }
+    }
+}
+
+impl Query {
+    /// クエリ文字列をパース
+    ///
+    /// `urgent!` のように比較演算子として読めない記号を含む語は、説明文の検索語になる。
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: input.chars().count() + 1,
+        };
+        let query = parser.parse_or()?;
+        match parser.peek() {
+            None => Ok(query),
+            Some((column, _)) => Err(query_error(column, "余分な `)` があります")),
+        }
+    }
+
+    /// タスクが条件を満たすかチェック（相対日付は `today` を基準にする）
+    pub fn matches(&self, todo: &Todo, today: NaiveDate) -> bool {
+        match self {
+            Query::And(a, b) => a.matches(todo, today) && b.matches(todo, today),
+            Query::Or(a, b) => a.matches(todo, today) || b.matches(todo, today),
+            Query::Not(q) => !q.matches(todo, today),
+            Query::Project(project) => todo.has_project(project),
+            Query::Context(context) => todo.has_context(context),
+            Query::Completed => todo.completed,
+            Query::Priority(min, max) => todo
+                .priority
+                .is_some_and(|priority| *min <= priority && priority <= *max),
+            Query::Text(text) => todo
+                .description
+                .to_lowercase()
+                .contains(&text.to_lowercase()),
+            Query::Compare { key, op, value } => compare(todo, key, *op, value, today),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = TodoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Query::parse(s)
+    }
+}
+
+impl TodoList {
+    /// クエリ文字列に一致するタスクを取得
+    ///
+    /// 書式は [`Query`] を参照。相対日付はリストの時計の今日を基準にする。
+    pub fn query(&self, query: &str) -> Result<Vec<&Todo>> {
+        let query = Query::parse(query)?;
+        let today = self.today();
+        Ok(self.filter(|todo| query.matches(todo, today)))
+    }
+}
+
+fn compare(todo: &Todo, key: &str, op: CompareOp, value: &QueryValue, today: NaiveDate) -> bool {
+    let actual = match key {
+        "created" => todo.creation_date.map(|date| date.to_string()),
+        "completed" => todo.completion_date.map(|date| date.to_string()),
+        _ => todo.get_tag(key).cloned(),
+    };
+    let Some(actual) = actual else {
+        return false;
+    };
+
+    let ordering = match value {
+        QueryValue::Date(date) => match (parse_date(&actual), date.resolve(today)) {
+            (Ok(actual), Some(expected)) => Some(actual.cmp(&expected)),
+            _ => None,
+        },
+        QueryValue::Number(expected) => actual
+            .parse::<f64>()
+            .ok()
+            .and_then(|actual| actual.partial_cmp(expected)),
+        QueryValue::Text(expected) => Some(actual.as_str().cmp(expected.as_str())),
+    };
+    ordering.is_some_and(|ordering| op.matches(ordering))
+}
+
+fn query_error(column: usize, message: impl Into<String>) -> TodoError {
+    TodoError::InvalidQuery {
+        column,
+        message: message.into(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Word(String),
+    Phrase(String),
+}
+
+/// 字句解析（列番号は 1 始まりの文字数）
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().enumerate().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let column = i + 1;
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push((column, Token::Open)),
+            ')' => tokens.push((column, Token::Close)),
+            '"' => {
+                let mut phrase = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, c)) => phrase.push(c),
+                        None => return Err(query_error(column, "`\"` が閉じられていません")),
+                    }
+                }
+                tokens.push((column, Token::Phrase(phrase)));
+            }
+            _ => {
+                let mut word = c.to_string();
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push((column, Token::Word(word)));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// 再帰下降パーサー
+///
+/// ```text
+/// or    := and ("or" and)*
+/// and   := unary ("and"? unary)*
+/// unary := "not" unary | "(" or ")" | atom
+/// ```
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    /// 入力の末尾の列（「途中で終わった」エラー用）
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(usize, &Token)> {
+        self.tokens
+            .get(self.pos)
+            .map(|(column, token)| (*column, token))
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some((_, Token::Word(word))) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<Query> {
+        let mut query = self.parse_and()?;
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            let rhs = self.parse_and()?;
+            query = Query::Or(Box::new(query), Box::new(rhs));
+        }
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> Result<Query> {
+        let mut query = self.parse_unary()?;
+        loop {
+            if self.peek_keyword("and") {
+                self.pos += 1;
+            } else if self.peek_keyword("or")
+                || matches!(self.peek(), None | Some((_, Token::Close)))
+            {
+                return Ok(query);
+            }
+            let rhs = self.parse_unary()?;
+            query = Query::And(Box::new(query), Box::new(rhs));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Query> {
+        let Some((column, token)) = self.peek() else {
+            return Err(query_error(self.end, "条件がありません"));
+        };
+        let token = token.clone();
+        self.pos += 1;
+
+        match token {
+            Token::Word(word) if word.eq_ignore_ascii_case("not") => {
+                Ok(Query::Not(Box::new(self.parse_unary()?)))
+            }
+            Token::Word(word)
+                if word.eq_ignore_ascii_case("and") || word.eq_ignore_ascii_case("or") =>
+            {
+                Err(query_error(
+                    column,
+                    format!("`{}` の前に条件がありません", word),
+                ))
+            }
+            Token::Open => {
+                let query = self.parse_or()?;
+                match self.peek() {
+                    Some((_, Token::Close)) => {
+                        self.pos += 1;
+                        Ok(query)
+                    }
+                    _ => Err(query_error(column, "`(` が閉じられていません")),
+                }
+            }
+            Token::Close => Err(query_error(column, "`)` の前に条件がありません")),
+            Token::Phrase(phrase) => Ok(Query::Text(phrase)),
+            Token::Word(word) => parse_atom(&word, column),
+        }
+    }
+}
+
+/// 演算子の候補（長いものから順に試す）
+const OPERATORS: [(&str, CompareOp); 7] = [
+    ("<=", CompareOp::Le),
+    (">=", CompareOp::Ge),
+    ("!=", CompareOp::Ne),
+    ("<", CompareOp::Lt),
+    (">", CompareOp::Gt),
+    ("=", CompareOp::Eq),
+    (":", CompareOp::Eq),
+];
+
+fn parse_atom(word: &str, column: usize) -> Result<Query> {
+    if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+        return Ok(Query::Project(project.to_string()));
+    }
+    if let Some(context) = word.strip_prefix('@').filter(|c| !c.is_empty()) {
+        return Ok(Query::Context(context.to_string()));
+    }
+    if word.eq_ignore_ascii_case("done") {
+        return Ok(Query::Completed);
+    }
+
+    let Some(split) = word.find(['<', '>', '=', '!', ':']) else {
+        return Ok(Query::Text(word.to_string()));
+    };
+    let key = &word[..split];
+    let rest = &word[split..];
+    // `urgent!` のように演算子として読めない記号を含む語は、説明文の検索語として扱う
+    let Some((symbol, op)) = OPERATORS
+        .iter()
+        .find(|(symbol, _)| rest.starts_with(symbol))
+    else {
+        return Ok(Query::Text(word.to_string()));
+    };
+    let value = &rest[symbol.len()..];
+    let value_column = column + word[..split + symbol.len()].chars().count();
+
+    if key.is_empty() {
+        return Err(query_error(column, format!("キーがありません: {}", word)));
+    }
+    if value.is_empty() {
+        return Err(query_error(
+            value_column,
+            format!("値がありません: {}", word),
+        ));
+    }
+
+    if key == "pri" {
+        let range = parse_priority_range(value)
+            .ok_or_else(|| query_error(value_column, format!("無効な優先度です: {}", value)))?;
+        return match op {
+            CompareOp::Eq => Ok(range),
+            CompareOp::Ne => Ok(Query::Not(Box::new(range))),
+            _ => Err(query_error(
+                column,
+                format!("優先度には `:` か `!=` を使ってください: {}", word),
+            )),
+        };
+    }
+
+    let value = parse_value(value).map_err(|message| query_error(value_column, message))?;
+    Ok(Query::Compare {
+        key: key.to_string(),
+        op: *op,
+        value,
+    })
+}
+
+fn parse_priority_range(value: &str) -> Option<Query> {
+    let letter = |s: &str| {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Priority::new(c.to_ascii_uppercase()),
+            _ => None,
+        }
+    };
+
+    let (min, max) = match value.split_once("..") {
+        Some((min, max)) => (letter(min)?, letter(max)?),
+        None => (letter(value)?, letter(value)?),
+    };
+    (min <= max).then_some(Query::Priority(min, max))
+}
+
+fn parse_value(value: &str) -> std::result::Result<QueryValue, String> {
+    if let Some(offset) = value.strip_prefix("today") {
+        let invalid = || format!("無効な相対日付です: {}", value);
+        let step = |step: &str| {
+            Recurrence::from_str(step)
+                .ok()
+                .filter(|step| !step.strict)
+                .ok_or_else(invalid)
+        };
+        let date = if offset.is_empty() {
+            DateValue::Today
+        } else if let Some(after) = offset.strip_prefix('+') {
+            DateValue::After(step(after)?)
+        } else if let Some(before) = offset.strip_prefix('-') {
+            DateValue::Before(step(before)?)
+        } else {
+            return Err(invalid());
+        };
+        return Ok(QueryValue::Date(date));
+    }
+
+    if let Ok(date) = parse_date(value) {
+        return Ok(QueryValue::Date(DateValue::Fixed(date)));
+    }
+    if let Ok(number) = value.parse::<f64>()
+        && number.is_finite()
+    {
+        return Ok(QueryValue::Number(number));
+    }
+    Ok(QueryValue::Text(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FixedClock;
+
+    fn list() -> TodoList {
+        let content = "\
+(A) Write report +Work @office due:2024-03-05
+(C) Weekly report +Work @home due:2024-03-20 est:3
+(D) Buy milk @store
+x 2024-03-01 Send invoice +Work due:2024-02-28 est:1.5
+Call mom 2024-02-01";
+        let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        TodoList::from_string(content)
+            .unwrap()
+            .with_clock(FixedClock::new(today))
+    }
+
+    fn found(list: &TodoList, query: &str) -> Vec<String> {
+        list.query(query)
+            .unwrap()
+            .into_iter()
+            .map(|todo| todo.description.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_ast() {
+        let query = Query::parse("+Work (@office or not done)").unwrap();
+        assert_eq!(
+            query,
+            Query::And(
+                Box::new(Query::Project("Work".to_string())),
+                Box::new(Query::Or(
+                    Box::new(Query::Context("office".to_string())),
+                    Box::new(Query::Not(Box::new(Query::Completed))),
+                )),
+            )
+        );
+    }
+
+    #[test]
+    fn test_example_query() {
+        let list = list();
+        assert_eq!(
+            found(
+                &list,
+                "+Work @office pri:A..C due<today+7d not done \"report\""
+            ),
+            ["Write report"]
+        );
+    }
+
+    #[test]
+    fn test_boolean_operators() {
+        let list = list();
+        assert_eq!(
+            found(&list, "@store or @home"),
+            ["Weekly report", "Buy milk"]
+        );
+        assert_eq!(found(&list, "+Work and done"), ["Send invoice"]);
+        assert_eq!(
+            found(&list, "not (+Work or @store)"),
+            ["Call mom 2024-02-01"]
+        );
+        assert_eq!(found(&list, "REPORT AND not weekly"), ["Write report"]);
+    }
+
+    #[test]
+    fn test_priority() {
+        let list = list();
+        assert_eq!(found(&list, "pri:a"), ["Write report"]);
+        assert_eq!(
+            found(&list, "pri:B..D not done"),
+            ["Weekly report", "Buy milk"]
+        );
+        assert_eq!(
+            found(&list, "pri!=A +Work"),
+            ["Weekly report", "Send invoice"]
+        );
+    }
+
+    #[test]
+    fn test_compare_values() {
+        let list = list();
+        assert_eq!(found(&list, "est>=2"), ["Weekly report"]);
+        assert_eq!(found(&list, "est<2"), ["Send invoice"]);
+        assert_eq!(found(&list, "due<today"), ["Send invoice"]);
+        assert_eq!(
+            found(&list, "due>=2024-03-05 due<=today+3w"),
+            ["Write report", "Weekly report"]
+        );
+        assert_eq!(found(&list, "due>today-1w due<today+1d"), ["Send invoice"]);
+        assert_eq!(found(&list, "completed:2024-03-01"), ["Send invoice"]);
+        assert_eq!(found(&list, "due:2024-03-05"), ["Write report"]);
+        // タグがない場合は != でも一致しない
+        assert_eq!(found(&list, "est!=3"), ["Send invoice"]);
+    }
+
+    #[test]
+    fn test_string_compare() {
+        let list = TodoList::from_string("A status:open\nB status:closed\nC").unwrap();
+        assert_eq!(found(&list, "status:open"), ["A"]);
+        assert_eq!(found(&list, "status<open"), ["B"]);
+    }
+
+    #[test]
+    fn test_words_with_operator_symbols() {
+        assert_eq!(
+            Query::parse("urgent!").unwrap(),
+            Query::Text("urgent!".to_string())
+        );
+        assert_eq!(
+            Query::parse("what?!").unwrap(),
+            Query::Text("what?!".to_string())
+        );
+        let list = TodoList::from_string("Fix it now urgent!\nLater").unwrap();
+        assert_eq!(found(&list, "urgent!"), ["Fix it now urgent!"]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |input: &str| match Query::parse(input) {
+            Err(TodoError::InvalidQuery { column, .. }) => column,
+            other => panic!("unexpected result: {other:?}"),
+        };
+
+        assert_eq!(error("+Work (done"), 7);
+        assert_eq!(error("+Work done)"), 11);
+        assert_eq!(error("+Work \"report"), 7);
+        assert_eq!(error("+Work or"), 9);
+        assert_eq!(error("or done"), 1);
+        assert_eq!(error("due<"), 5);
+        assert_eq!(error("pri:A..Z1"), 5);
+        assert_eq!(error("pri>A"), 1);
+        assert_eq!(error("due<today+0d"), 5);
+        assert_eq!(error("due<todayé"), 5);
+        assert_eq!(error("due<todays"), 5);
+        assert_eq!(error(""), 1);
+    }
+}
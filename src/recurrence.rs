@@ -0,0 +1,193 @@
+use crate::error::{Result, TodoError};
+use chrono::{Days, Months, NaiveDate};
+use std::fmt;
+use std::str::FromStr;
+
+/// 繰り返しの単位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecurrenceUnit {
+    /// 日 (`d`)
+    Day,
+    /// 週 (`w`)
+    Week,
+    /// 月 (`m`)
+    Month,
+    /// 年 (`y`)
+    Year,
+}
+
+impl RecurrenceUnit {
+    fn as_char(self) -> char {
+        match self {
+            RecurrenceUnit::Day => 'd',
+            RecurrenceUnit::Week => 'w',
+            RecurrenceUnit::Month => 'm',
+            RecurrenceUnit::Year => 'y',
+        }
+    }
+}
+
+/// `rec:` タグで指定する繰り返し間隔
+///
+/// `rec:1w` のように指定した場合は完了日を基準に、
+/// `rec:+1w` のように `+` を付けた場合（厳密モード）は元の期限日を基準に次の日付を決める。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Recurrence {
+    /// 元の日付を基準にするかどうか (`+` 付き)
+    pub strict: bool,
+
+    /// 間隔の数
+    pub amount: u32,
+
+    /// 間隔の単位
+    pub unit: RecurrenceUnit,
+}
+
+impl Recurrence {
+    /// 新しい繰り返し間隔を作成
+    pub fn new(amount: u32, unit: RecurrenceUnit) -> Self {
+        Self {
+            strict: false,
+            amount,
+            unit,
+        }
+    }
+
+    /// 厳密モード（元の日付を基準にする）を設定
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// 指定日から 1 間隔分進めた日付を返す
+    ///
+    /// 月末の日付を月単位で進める場合は、その月の末日に丸められる。
+    pub fn advance(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self.unit {
+            RecurrenceUnit::Day => date.checked_add_days(Days::new(self.amount.into())),
+            RecurrenceUnit::Week => date.checked_add_days(Days::new(u64::from(self.amount) * 7)),
+            RecurrenceUnit::Month => date.checked_add_months(Months::new(self.amount)),
+            RecurrenceUnit::Year => {
+                date.checked_add_months(Months::new(self.amount.checked_mul(12)?))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.strict {
+            write!(f, "+")?;
+        }
+        write!(f, "{}{}", self.amount, self.unit.as_char())
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = TodoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || TodoError::InvalidRecurrence(s.to_string());
+
+        let (strict, rest) = match s.strip_prefix('+') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+
+        let mut chars = rest.chars();
+        let unit = match chars.next_back() {
+            Some('d') => RecurrenceUnit::Day,
+            Some('w') => RecurrenceUnit::Week,
+            Some('m') => RecurrenceUnit::Month,
+            Some('y') => RecurrenceUnit::Year,
+            _ => return Err(invalid()),
+        };
+
+        // 数値を省略した場合は 1 とみなす
+        let amount = match chars.as_str() {
+            "" => 1,
+            digits if digits.bytes().all(|b| b.is_ascii_digit()) => {
+                digits.parse().map_err(|_| invalid())?
+            }
+            _ => return Err(invalid()),
+        };
+
+        if amount == 0 {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            strict,
+            amount,
+            unit,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse_recurrence() {
+        assert_eq!(
+            "1w".parse::<Recurrence>().unwrap(),
+            Recurrence::new(1, RecurrenceUnit::Week)
+        );
+        assert_eq!(
+            "+3d".parse::<Recurrence>().unwrap(),
+            Recurrence::new(3, RecurrenceUnit::Day).strict()
+        );
+        assert_eq!(
+            "m".parse::<Recurrence>().unwrap(),
+            Recurrence::new(1, RecurrenceUnit::Month)
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_recurrence() {
+        for s in ["", "+", "1", "0d", "1x", "-1d", "1.5w", "+-1d"] {
+            assert!(
+                matches!(
+                    s.parse::<Recurrence>(),
+                    Err(TodoError::InvalidRecurrence(_))
+                ),
+                "{s}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_display_recurrence() {
+        assert_eq!(Recurrence::new(2, RecurrenceUnit::Year).to_string(), "2y");
+        assert_eq!(
+            Recurrence::new(3, RecurrenceUnit::Day).strict().to_string(),
+            "+3d"
+        );
+    }
+
+    #[test]
+    fn test_advance() {
+        let d = date(2024, 1, 31);
+        assert_eq!(
+            Recurrence::new(3, RecurrenceUnit::Day).advance(d),
+            Some(date(2024, 2, 3))
+        );
+        assert_eq!(
+            Recurrence::new(1, RecurrenceUnit::Week).advance(d),
+            Some(date(2024, 2, 7))
+        );
+        assert_eq!(
+            Recurrence::new(1, RecurrenceUnit::Month).advance(d),
+            Some(date(2024, 2, 29))
+        );
+        assert_eq!(
+            Recurrence::new(1, RecurrenceUnit::Year).advance(date(2024, 2, 29)),
+            Some(date(2025, 2, 28))
+        );
+    }
+}
@@ -0,0 +1,384 @@
+use crate::csv::escape_field;
+use crate::{Todo, TodoList};
+use chrono::{Datelike, NaiveDate};
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+/// 集計のグループ分けの方法
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupBy {
+    /// プロジェクト（複数あるタスクはそれぞれに数える）
+    Project,
+    /// コンテキスト（複数あるタスクはそれぞれに数える）
+    Context,
+    /// 優先度
+    Priority,
+    /// タグの値
+    Tag(String),
+    /// 作成日の ISO 週 (`2024-W09`)
+    CreationWeek,
+    /// 完了日の月 (`2024-03`)
+    CompletionMonth,
+}
+
+impl GroupBy {
+    /// グループのキーを取得（空の場合は「なし」のグループに入る）
+    pub(crate) fn keys(&self, todo: &Todo) -> Vec<String> {
+        match self {
+            GroupBy::Project => todo.projects.clone(),
+            GroupBy::Context => todo.contexts.clone(),
+            GroupBy::Priority => todo
+                .priority
+                .map(|p| p.as_char().to_string())
+                .into_iter()
+                .collect(),
+            GroupBy::Tag(key) => todo.get_tag(key).cloned().into_iter().collect(),
+            GroupBy::CreationWeek => todo
+                .creation_date
+                .map(|date| {
+                    let week = date.iso_week();
+                    format!("{:04}-W{:02}", week.year(), week.week())
+                })
+                .into_iter()
+                .collect(),
+            GroupBy::CompletionMonth => todo
+                .completion_date
+                .map(|date| date.format("%Y-%m").to_string())
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroupBy::Project => write!(f, "project"),
+            GroupBy::Context => write!(f, "context"),
+            GroupBy::Priority => write!(f, "priority"),
+            GroupBy::Tag(key) => write!(f, "tag:{}", key),
+            GroupBy::CreationWeek => write!(f, "creation_week"),
+            GroupBy::CompletionMonth => write!(f, "completion_month"),
+        }
+    }
+}
+
+/// グループごとの集計結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupStats {
+    /// グループのキー（該当する値がないタスクのグループは `None`）
+    pub key: Option<String>,
+    /// 未完了のタスク数
+    pub open: usize,
+    /// 完了済みのタスク数
+    pub done: usize,
+    /// 未完了タスクの作成日からの平均経過日数
+    pub average_age: Option<f64>,
+    /// 完了タスクの作成日から完了日までの平均日数
+    pub average_lead_time: Option<f64>,
+}
+
+impl GroupStats {
+    /// タスクの総数
+    pub fn total(&self) -> usize {
+        self.open + self.done
+    }
+
+    /// 完了率（0.0〜1.0）
+    pub fn completion_rate(&self) -> f64 {
+        if self.total() == 0 {
+            0.0
+        } else {
+            self.done as f64 / self.total() as f64
+        }
+    }
+}
+
+/// グループ分けした集計レポート
+///
+/// [`Display`](fmt::Display) でテキストとして、
+/// [`to_csv`](Report::to_csv) / [`to_json`](Report::to_json) で機械可読な形式で出力できる。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// グループ分けの方法
+    pub group_by: GroupBy,
+    /// キーの順に並べたグループ（「なし」のグループは最後）
+    pub groups: Vec<GroupStats>,
+}
+
+impl Report {
+    /// CSV として出力（ヘッダー行付き、改行は LF）
+    pub fn to_csv(&self) -> String {
+        let mut out =
+            String::from("group,open,done,completion_rate,average_age,average_lead_time\n");
+        for group in &self.groups {
+            let key = group.key.as_deref().unwrap_or("");
+            let optional = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{}",
+                escape_field(key, ','),
+                group.open,
+                group.done,
+                group.completion_rate(),
+                optional(group.average_age),
+                optional(group.average_lead_time),
+            );
+        }
+        out
+    }
+
+    /// JSON として出力
+    pub fn to_json(&self) -> String {
+        let optional = |value: Option<f64>| {
+            value
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "null".to_string())
+        };
+
+        let groups: Vec<String> = self
+            .groups
+            .iter()
+            .map(|group| {
+                format!(
+                    "{{\"key\":{},\"open\":{},\"done\":{},\"completion_rate\":{},\"average_age\":{},\"average_lead_time\":{}}}",
+                    group
+                        .key
+                        .as_deref()
+                        .map(json_string)
+                        .unwrap_or_else(|| "null".to_string()),
+                    group.open,
+                    group.done,
+                    group.completion_rate(),
+                    optional(group.average_age),
+                    optional(group.average_lead_time),
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"group_by\":{},\"groups\":[{}]}}",
+            json_string(&self.group_by.to_string()),
+            groups.join(",")
+        )
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for group in &self.groups {
+            write!(
+                f,
+                "{}: 未完了 {} / 完了 {} (完了率 {:.1}%)",
+                group.key.as_deref().unwrap_or("(なし)"),
+                group.open,
+                group.done,
+                group.completion_rate() * 100.0
+            )?;
+            if let Some(age) = group.average_age {
+                write!(f, "、平均経過 {:.1}日", age)?;
+            }
+            if let Some(lead_time) = group.average_lead_time {
+                write!(f, "、平均リードタイム {:.1}日", lead_time)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl TodoList {
+    /// タスクをグループ分けして集計
+    ///
+    /// 経過日数はリストの時計の今日を基準にする。
+    pub fn report(&self, group_by: GroupBy) -> Report {
+        self.report_on(group_by, self.today())
+    }
+
+    /// 指定した日を今日としてタスクをグループ分けして集計
+    pub fn report_on(&self, group_by: GroupBy, today: NaiveDate) -> Report {
+        let mut groups: BTreeMap<Option<String>, Accumulator> = BTreeMap::new();
+
+        for todo in self.iter() {
+            let keys = group_by.keys(todo);
+            if keys.is_empty() {
+                groups.entry(None).or_default().add(todo, today);
+            }
+            for key in keys {
+                groups.entry(Some(key)).or_default().add(todo, today);
+            }
+        }
+
+        // BTreeMap では None が先頭になるので、最後に回す
+        let mut groups: Vec<GroupStats> = groups
+            .into_iter()
+            .map(|(key, acc)| acc.finish(key))
+            .collect();
+        if groups.first().is_some_and(|group| group.key.is_none()) {
+            let none = groups.remove(0);
+            groups.push(none);
+        }
+
+        Report { group_by, groups }
+    }
+}
+
+#[derive(Default)]
+struct Accumulator {
+    open: usize,
+    done: usize,
+    ages: Vec<i64>,
+    lead_times: Vec<i64>,
+}
+
+impl Accumulator {
+    fn add(&mut self, todo: &Todo, today: NaiveDate) {
+        if todo.completed {
+            self.done += 1;
+            if let (Some(created), Some(completed)) = (todo.creation_date, todo.completion_date) {
+                self.lead_times.push((completed - created).num_days());
+            }
+        } else {
+            self.open += 1;
+            if let Some(created) = todo.creation_date {
+                self.ages.push((today - created).num_days());
+            }
+        }
+    }
+
+    fn finish(self, key: Option<String>) -> GroupStats {
+        let average = |values: &[i64]| {
+            (!values.is_empty()).then(|| values.iter().sum::<i64>() as f64 / values.len() as f64)
+        };
+        GroupStats {
+            key,
+            open: self.open,
+            done: self.done,
+            average_age: average(&self.ages),
+            average_lead_time: average(&self.lead_times),
+        }
+    }
+}
+
+/// JSON の文字列リテラルを作る
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "\
+2024-02-20 Write spec +Work @office
+x 2024-02-28 2024-02-20 Review spec +Work
+2024-02-26 Buy milk @store
+x 2024-03-02 2024-03-01 Deploy +Work +Ops @office
+(A) Call mom";
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()
+    }
+
+    fn report(group_by: GroupBy) -> Report {
+        TodoList::from_string(CONTENT)
+            .unwrap()
+            .report_on(group_by, today())
+    }
+
+    fn keys(report: &Report) -> Vec<Option<&str>> {
+        report.groups.iter().map(|g| g.key.as_deref()).collect()
+    }
+
+    #[test]
+    fn test_group_by_project() {
+        let report = report(GroupBy::Project);
+        assert_eq!(keys(&report), [Some("Ops"), Some("Work"), None]);
+
+        let work = &report.groups[1];
+        assert_eq!((work.open, work.done), (1, 2));
+        assert!((work.completion_rate() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(work.average_age, Some(10.0));
+        assert_eq!(work.average_lead_time, Some(4.5));
+
+        let none = &report.groups[2];
+        assert_eq!((none.open, none.done), (2, 0));
+        assert_eq!(none.average_age, Some(4.0));
+        assert_eq!(none.average_lead_time, None);
+    }
+
+    #[test]
+    fn test_group_by_other_keys() {
+        assert_eq!(
+            keys(&report(GroupBy::Context)),
+            [Some("office"), Some("store"), None]
+        );
+        assert_eq!(keys(&report(GroupBy::Priority)), [Some("A"), None]);
+        assert_eq!(
+            keys(&report(GroupBy::CreationWeek)),
+            [Some("2024-W08"), Some("2024-W09"), None]
+        );
+        assert_eq!(
+            keys(&report(GroupBy::CompletionMonth)),
+            [Some("2024-02"), Some("2024-03"), None]
+        );
+    }
+
+    #[test]
+    fn test_group_by_tag() {
+        let list = TodoList::from_string("A size:s\nB size:l\nx C size:s\nD").unwrap();
+        let report = list.report_on(GroupBy::Tag("size".to_string()), today());
+        assert_eq!(keys(&report), [Some("l"), Some("s"), None]);
+        assert_eq!((report.groups[1].open, report.groups[1].done), (1, 1));
+    }
+
+    #[test]
+    fn test_text_output() {
+        let report = report(GroupBy::Priority);
+        assert_eq!(
+            report.to_string(),
+            "A: 未完了 1 / 完了 0 (完了率 0.0%)\n\
+             (なし): 未完了 2 / 完了 2 (完了率 50.0%)、平均経過 7.0日、平均リードタイム 4.5日\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_output() {
+        let list = TodoList::from_string("A size:\"big,heavy\"\nx B").unwrap();
+        let report = list.report_on(GroupBy::Tag("size".to_string()), today());
+        assert_eq!(
+            report.to_csv(),
+            "group,open,done,completion_rate,average_age,average_lead_time\n\
+             \"\"\"big,heavy\"\"\",1,0,0,,\n\
+             ,0,1,1,,\n"
+        );
+    }
+
+    #[test]
+    fn test_json_output() {
+        let report = report(GroupBy::Priority);
+        assert_eq!(
+            report.to_json(),
+            "{\"group_by\":\"priority\",\"groups\":[\
+             {\"key\":\"A\",\"open\":1,\"done\":0,\"completion_rate\":0,\"average_age\":null,\"average_lead_time\":null},\
+             {\"key\":null,\"open\":2,\"done\":2,\"completion_rate\":0.5,\"average_age\":7,\"average_lead_time\":4.5}]}"
+        );
+        assert_eq!(json_string("a\"b\\\n\u{1}"), "\"a\\\"b\\\\\\n\\u0001\"");
+    }
+}
@@ -0,0 +1,466 @@
+use crate::merge::merge_lines;
+use crate::{ParsePolicy, Result, TodoError, TodoList};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 改行コード
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+impl LineEnding {
+    /// 改行コードの文字列を取得
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+
+    /// 内容の最初の改行から改行コードを判定する
+    pub(crate) fn detect(content: &str) -> Option<Self> {
+        let pos = content.find('\n')?;
+        if content[..pos].ends_with('\r') {
+            Some(LineEnding::CrLf)
+        } else {
+            Some(LineEnding::Lf)
+        }
+    }
+}
+
+/// ファイル保存時のオプション
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveOptions {
+    /// 保持するバックアップの世代数
+    pub backups: usize,
+
+    /// 既存ファイルのパーミッションを引き継ぐかどうか
+    pub keep_permissions: bool,
+
+    /// 改行コード (`None` の場合は読み込み時の改行コードを維持する)
+    pub line_ending: Option<LineEnding>,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SaveOptions {
+    /// デフォルトのオプションを作成
+    ///
+    /// バックアップなし、パーミッションを引き継ぎ、改行コードを維持する。
+    pub fn new() -> Self {
+        Self {
+            backups: 0,
+            keep_permissions: true,
+            line_ending: None,
+        }
+    }
+
+    /// 保持するバックアップの世代数を設定
+    ///
+    /// 最新のバックアップは `todo.txt.bak`、それより古いものは
+    /// `todo.txt.bak.2`, `todo.txt.bak.3`, ... に保存される。
+    pub fn with_backups(mut self, generations: usize) -> Self {
+        self.backups = generations;
+        self
+    }
+
+    /// 既存ファイルのパーミッションを引き継ぐかどうかを設定
+    pub fn with_keep_permissions(mut self, keep: bool) -> Self {
+        self.keep_permissions = keep;
+        self
+    }
+
+    /// 改行コードを設定
+    pub fn with_line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = Some(line_ending);
+        self
+    }
+}
+
+impl TodoList {
+    /// TodoList をファイルに保存
+    ///
+    /// 一時ファイルに書き込んでから置き換えるため、
+    /// 書き込み中にクラッシュしても元のファイルが壊れることはない。
+    ///
+    /// [`TodoList::from_file`] で読み込んだファイルに保存する場合、
+    /// 読み込み後にファイルが外部で変更されていれば行単位の 3-way マージを行い、
+    /// マージ結果をこのリストにも反映する。
+    /// 競合があった場合はファイルを変更せずに [`TodoError::MergeConflict`] を返す。
+    pub fn save_to_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.save_to_file_with(path, &SaveOptions::default())
+    }
+
+    /// オプションを指定して TodoList をファイルに保存
+    pub fn save_to_file_with<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        options: &SaveOptions,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let line_ending = options.line_ending.unwrap_or(self.line_ending());
+        let mut content = self.to_file_content(line_ending);
+
+        let merged = match self.external_changes(path)? {
+            Some((base, theirs)) => {
+                let base: Vec<&str> = base.lines().collect();
+                let ours: Vec<&str> = content.lines().collect();
+                let theirs: Vec<&str> = theirs.lines().collect();
+                let lines = merge_lines(&base, &ours, &theirs).map_err(TodoError::MergeConflict)?;
+
+                content = lines.join(line_ending.as_str());
+                if self.has_final_newline() && !content.is_empty() {
+                    content.push_str(line_ending.as_str());
+                }
+                true
+            }
+            None => false,
+        };
+
+        write_atomic(path, content.as_bytes(), options)?;
+
+        if merged {
+            let report = TodoList::from_string_with(&content, ParsePolicy::Preserve)?;
+            self.replace_entries(report.list);
+        }
+        self.set_baseline(path, content);
+
+        Ok(())
+    }
+
+    /// 読み込み後にファイルが外部で変更されているかチェック
+    ///
+    /// このリストが指定したファイルから読み込まれていない場合は `false` を返す。
+    pub fn has_external_changes<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+        Ok(self.external_changes(path.as_ref())?.is_some())
+    }
+
+    /// 外部で変更されている場合に、マージの基準となる内容と現在の内容を返す
+    fn external_changes(&self, path: &Path) -> Result<Option<(&str, String)>> {
+        let Some(baseline) = self.baseline_for(path) else {
+            return Ok(None);
+        };
+
+        match fs::read_to_string(path) {
+            Ok(current) if current != baseline.content => {
+                Ok(Some((baseline.merge_base.as_str(), current)))
+            }
+            Ok(_) => Ok(None),
+            // 削除されている場合はそのまま書き込む
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// ファイルに書き込む内容を生成
+    pub(crate) fn to_file_content(&self, line_ending: LineEnding) -> String {
+        let mut content = self.to_string();
+        if line_ending == LineEnding::CrLf {
+            content = content.replace('\n', "\r\n");
+        }
+        if self.has_final_newline() && !content.is_empty() {
+            content.push_str(line_ending.as_str());
+        }
+        content
+    }
+}
+
+/// 一時ファイルへの書き込み・fsync・リネームでファイルを置き換える
+pub(crate) fn write_atomic(path: &Path, content: &[u8], options: &SaveOptions) -> Result<()> {
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("ファイル名がありません: {}", path.display()),
+        )
+    })?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let existing = fs::metadata(path).ok();
+
+    // 同じプロセス内で同時に保存しても一時ファイルが重ならないよう、呼び出しごとに番号を付ける
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp_path = dir.join(temp_name);
+
+    let written = (|| -> io::Result<()> {
+        let _ = fs::remove_file(&temp_path);
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)?;
+        file.write_all(content)?;
+        if options.keep_permissions
+            && let Some(metadata) = &existing
+        {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()?;
+
+        if options.backups > 0 && existing.is_some() {
+            rotate_backups(path, options.backups)?;
+        }
+
+        fs::rename(&temp_path, path)?;
+        sync_dir(dir)
+    })();
+
+    if written.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    Ok(written?)
+}
+
+/// `generation` 世代目のバックアップのパス
+fn backup_path(path: &Path, generation: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bak");
+    if generation > 1 {
+        name.push(format!(".{}", generation));
+    }
+    PathBuf::from(name)
+}
+
+/// バックアップを 1 世代ずつずらし、現在のファイルを最新のバックアップにする
+fn rotate_backups(path: &Path, generations: usize) -> io::Result<()> {
+    match fs::remove_file(backup_path(path, generations)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+
+    for generation in (1..generations).rev() {
+        let from = backup_path(path, generation);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, generation + 1))?;
+        }
+    }
+
+    fs::copy(path, backup_path(path, 1))?;
+    Ok(())
+}
+
+/// リネームを永続化するためにディレクトリを fsync する
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    fs::File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn test_detect_line_ending() {
+        assert_eq!(LineEnding::detect("a\r\nb"), Some(LineEnding::CrLf));
+        assert_eq!(LineEnding::detect("a\nb\r\n"), Some(LineEnding::Lf));
+        assert_eq!(LineEnding::detect("a"), None);
+    }
+
+    #[test]
+    fn test_save_keeps_line_endings() {
+        let dir = temp_dir("save-line-endings");
+        let path = dir.join("todo.txt");
+
+        let content = "(A) Task 1\r\nTask 2 +Work\r\n";
+        let mut list = TodoList::from_string(content).unwrap();
+        list.save_to_file(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+
+        let options = SaveOptions::new().with_line_ending(LineEnding::Lf);
+        list.save_to_file_with(&path, &options).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "(A) Task 1\nTask 2 +Work\n"
+        );
+
+        let entries: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_rotates_backups() {
+        let dir = temp_dir("save-backups");
+        let path = dir.join("todo.txt");
+        let options = SaveOptions::new().with_backups(2);
+
+        for i in 1..=4 {
+            let mut list = TodoList::from_string(&format!("Task {}\n", i)).unwrap();
+            list.save_to_file_with(&path, &options).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Task 4\n");
+        assert_eq!(
+            fs::read_to_string(dir.join("todo.txt.bak")).unwrap(),
+            "Task 3\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("todo.txt.bak.2")).unwrap(),
+            "Task 2\n"
+        );
+        assert!(!dir.join("todo.txt.bak.3").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir("save-permissions");
+        let path = dir.join("todo.txt");
+        fs::write(&path, "Task 1\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        let mut list = TodoList::from_file(&path).unwrap();
+        list.save_to_file(&path).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_writes_use_separate_temp_files() {
+        let dir = temp_dir("save-concurrent");
+        let path = dir.join("todo.txt");
+        let contents = ["A\n".repeat(100_000), "B\n".repeat(100_000)];
+
+        for _ in 0..10 {
+            std::thread::scope(|scope| {
+                for content in &contents {
+                    let path = &path;
+                    scope.spawn(move || {
+                        write_atomic(path, content.as_bytes(), &SaveOptions::new()).unwrap()
+                    });
+                }
+            });
+            let saved = fs::read_to_string(&path).unwrap();
+            assert!(contents.contains(&saved));
+        }
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_to_missing_directory_fails() {
+        let dir = temp_dir("save-missing");
+        let mut list = TodoList::from_string("Task 1").unwrap();
+        assert!(
+            list.save_to_file(dir.join("missing").join("todo.txt"))
+                .is_err()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_merges_external_changes() {
+        let dir = temp_dir("save-merge");
+        let path = dir.join("todo.txt");
+        fs::write(&path, "Task 1\nTask 2\nTask 3\n").unwrap();
+
+        let mut list = TodoList::from_file(&path).unwrap();
+        list.get_mut(0).unwrap().add_project("Work");
+        assert!(!list.has_external_changes(&path).unwrap());
+
+        fs::write(&path, "Task 1\nTask 2\nTask 3 @home\nTask 4\n").unwrap();
+        assert!(list.has_external_changes(&path).unwrap());
+
+        let id = list.id_at(0).unwrap();
+        list.save_to_file(&path).unwrap();
+        assert_eq!(list.index_of(id), Some(0));
+        let expected = "Task 1 +Work\nTask 2\nTask 3 @home\nTask 4\n";
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+        assert_eq!(list.len(), 4);
+        assert!(!list.has_external_changes(&path).unwrap());
+
+        list.remove(1).unwrap();
+        list.save_to_file(&path).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "Task 1 +Work\nTask 3 @home\nTask 4\n"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_merges_file_with_blank_lines() {
+        let dir = temp_dir("save-merge-blank");
+        let path = dir.join("todo.txt");
+        fs::write(&path, "A\n\nB\n").unwrap();
+
+        let mut list = TodoList::from_file(&path).unwrap();
+        fs::write(&path, "A\n\nB changed\n").unwrap();
+        list.save_to_file(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "A\n\nB changed\n");
+
+        let mut list = TodoList::from_file(&path).unwrap();
+        list.get_mut(0).unwrap().add_context("home");
+        fs::write(&path, "A\n\nB changed\nC\n").unwrap();
+        list.save_to_file(&path).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "A @home\n\nB changed\nC\n"
+        );
+        assert_eq!(list.len(), 3);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_reports_conflicts() {
+        let dir = temp_dir("save-conflict");
+        let path = dir.join("todo.txt");
+        fs::write(&path, "Task 1\nTask 2\n").unwrap();
+
+        let mut list = TodoList::from_file(&path).unwrap();
+        list.get_mut(1).unwrap().add_context("phone");
+        fs::write(&path, "Task 1\nTask 2 @email\n").unwrap();
+
+        match list.save_to_file(&path) {
+            Err(TodoError::MergeConflict(conflicts)) => {
+                assert_eq!(conflicts.len(), 1);
+                assert_eq!(conflicts[0].line, 2);
+                assert_eq!(conflicts[0].ours, ["Task 2 @phone"]);
+                assert_eq!(conflicts[0].theirs, ["Task 2 @email"]);
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "Task 1\nTask 2 @email\n"
+        );
+
+        list.clear_baseline();
+        list.save_to_file(&path).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "Task 1\nTask 2 @phone\n"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,348 @@
+use crate::{TaskId, TodoList};
+use std::ops::Range;
+
+/// 検索の設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchOptions {
+    /// 部分一致しない語をあいまい検索（編集距離）で探すかどうか
+    pub fuzzy: bool,
+
+    /// あいまい検索で許す編集距離（`None` の場合は語の長さから決める）
+    pub max_typos: Option<usize>,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            fuzzy: true,
+            max_typos: None,
+        }
+    }
+}
+
+impl SearchOptions {
+    /// デフォルトの設定を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// あいまい検索の有無を設定
+    pub fn with_fuzzy(mut self, fuzzy: bool) -> Self {
+        self.fuzzy = fuzzy;
+        self
+    }
+
+    /// あいまい検索で許す編集距離を設定
+    pub fn with_max_typos(mut self, max_typos: usize) -> Self {
+        self.max_typos = Some(max_typos);
+        self
+    }
+
+    fn max_typos_for(&self, term_len: usize) -> usize {
+        if !self.fuzzy {
+            return 0;
+        }
+        self.max_typos.unwrap_or(match term_len {
+            0..=2 => 0,
+            3..=5 => 1,
+            _ => 2,
+        })
+    }
+}
+
+/// 検索結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    /// リスト内のインデックス
+    pub index: usize,
+    /// タスクの ID
+    pub id: TaskId,
+    /// 関連度（0.0〜1.0、完全な部分一致のみなら 1.0）
+    pub score: f64,
+    /// 説明文の中で一致した部分（バイト範囲、昇順）
+    pub highlights: Vec<Range<usize>>,
+}
+
+impl TodoList {
+    /// 説明文を検索し、関連度の高い順に返す
+    ///
+    /// クエリは空白で語に分けられ、すべての語が一致したタスクだけが結果に含まれる。
+    /// 大文字小文字・アクセント記号・全角半角・カタカナとひらがなの違いは無視される。
+    ///
+    /// ```
+    /// use todo_rs::TodoList;
+    ///
+    /// let list = TodoList::from_string("Café meeting\nレビュー依頼\nCall mom").unwrap();
+    /// assert_eq!(list.search("cafe")[0].index, 0);
+    /// assert_eq!(list.search("れびゅー")[0].index, 1);
+    /// assert_eq!(list.search("meetnig")[0].index, 0);
+    /// ```
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        self.search_with(query, &SearchOptions::default())
+    }
+
+    /// 設定を指定して説明文を検索
+    pub fn search_with(&self, query: &str, options: &SearchOptions) -> Vec<SearchHit> {
+        let terms: Vec<Vec<char>> = query
+            .split_whitespace()
+            .map(|term| normalize(term).into_iter().map(|(c, _)| c).collect())
+            .filter(|term: &Vec<char>| !term.is_empty())
+            .collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut hits: Vec<SearchHit> = self
+            .iter_with_ids()
+            .enumerate()
+            .filter_map(|(index, (id, todo))| {
+                let text = normalize(&todo.description);
+                let chars: Vec<char> = text.iter().map(|(c, _)| *c).collect();
+
+                let mut score = 0.0;
+                let mut highlights = Vec::new();
+                for term in &terms {
+                    let (span, term_score) = match_term(&chars, term, options)?;
+                    score += term_score;
+                    highlights.push(text[span.start].1.start..text[span.end - 1].1.end);
+                }
+
+                Some(SearchHit {
+                    index,
+                    id,
+                    score: score / terms.len() as f64,
+                    highlights: merge_ranges(highlights),
+                })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.index.cmp(&b.index)));
+        hits
+    }
+}
+
+/// 語を探し、一致した範囲（正規化後の文字位置）と点数を返す
+fn match_term(
+    text: &[char],
+    term: &[char],
+    options: &SearchOptions,
+) -> Option<(Range<usize>, f64)> {
+    if let Some(start) = text.windows(term.len()).position(|window| window == term) {
+        return Some((start..start + term.len(), 1.0));
+    }
+
+    let max_typos = options.max_typos_for(term.len());
+    if max_typos == 0 {
+        return None;
+    }
+    let (span, distance) = closest_substring(text, term)?;
+    if distance > max_typos || span.is_empty() {
+        return None;
+    }
+    // あいまい一致は部分一致より必ず低くなるようにする
+    Some((
+        span,
+        0.5 * (1.0 - distance as f64 / (term.len() + 1) as f64),
+    ))
+}
+
+/// 編集距離が最小になる部分文字列の範囲とその距離を求める (Sellers のアルゴリズム)
+fn closest_substring(text: &[char], term: &[char]) -> Option<(Range<usize>, usize)> {
+    let width = text.len() + 1;
+    // (距離, 一致の開始位置)
+    let mut previous: Vec<(usize, usize)> = (0..width).map(|j| (0, j)).collect();
+
+    for (i, &t) in term.iter().enumerate() {
+        let mut current = vec![(i + 1, 0); width];
+        for j in 1..width {
+            let substitute = (
+                previous[j - 1].0 + usize::from(text[j - 1] != t),
+                previous[j - 1].1,
+            );
+            let delete = (previous[j].0 + 1, previous[j].1);
+            let insert = (current[j - 1].0 + 1, current[j - 1].1);
+            current[j] = substitute.min(delete).min(insert);
+        }
+        previous = current;
+    }
+
+    previous
+        .iter()
+        .enumerate()
+        .skip(1)
+        // 距離が同じなら、長さが語に近いものを選ぶ
+        .min_by_key(|(end, (distance, start))| (*distance, (end - start).abs_diff(term.len())))
+        .map(|(end, &(distance, start))| (start..end, distance))
+}
+
+/// 重なる・隣接する範囲をまとめる
+fn merge_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// 検索用に文字列を正規化し、各文字に元の文字列のバイト範囲を対応付ける
+fn normalize(s: &str) -> Vec<(char, Range<usize>)> {
+    let mut out = Vec::with_capacity(s.len());
+    for (start, c) in s.char_indices() {
+        let range = start..start + c.len_utf8();
+        for lower in c.to_lowercase() {
+            match fold_char(lower) {
+                Folded::One(c) => out.push((c, range.clone())),
+                Folded::Two(a, b) => {
+                    out.push((a, range.clone()));
+                    out.push((b, range.clone()));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// 比較用に文字列を正規化する（小文字化し、アクセント記号・全角英数字・カタカナを畳み込む）
+pub(crate) fn fold(s: &str) -> String {
+    normalize(s).into_iter().map(|(c, _)| c).collect()
+}
+
+enum Folded {
+    One(char),
+    Two(char, char),
+}
+
+/// アクセント記号・全角英数字・カタカナを畳み込む（小文字化した後の文字を受け取る）
+fn fold_char(c: char) -> Folded {
+    let folded = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'ď' | 'đ' => 'd',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'ĥ' | 'ħ' => 'h',
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => 'i',
+        'ĵ' => 'j',
+        'ķ' => 'k',
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => 'l',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => 'o',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'ś' | 'ŝ' | 'ş' | 'š' => 's',
+        'ţ' | 'ť' | 'ŧ' => 't',
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+        'ŵ' => 'w',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        'ß' => return Folded::Two('s', 's'),
+        'æ' => return Folded::Two('a', 'e'),
+        'œ' => return Folded::Two('o', 'e'),
+        // 全角スペース
+        '\u{3000}' => ' ',
+        // 全角英数字・記号 (！〜～) を半角に（小文字化済み）
+        '\u{FF01}'..='\u{FF5E}' => {
+            let c = char::from_u32(c as u32 - 0xFEE0).unwrap_or(c);
+            c.to_ascii_lowercase()
+        }
+        // カタカナ (ァ〜ヶ) をひらがなに
+        '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        c => c,
+    };
+    Folded::One(folded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalized(s: &str) -> String {
+        normalize(s).into_iter().map(|(c, _)| c).collect()
+    }
+
+    fn spans(hit: &SearchHit) -> Vec<(usize, usize)> {
+        hit.highlights.iter().map(|r| (r.start, r.end)).collect()
+    }
+
+    fn indices(hits: &[SearchHit]) -> Vec<usize> {
+        hits.iter().map(|hit| hit.index).collect()
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalized("Crème Brûlée"), "creme brulee");
+        assert_eq!(normalized("Straße"), "strasse");
+        assert_eq!(normalized("ＴＯＤＯ　１２３"), "todo 123");
+        assert_eq!(normalized("カタカナとひらがな"), "かたかなとひらがな");
+        assert_eq!(normalized("ヴァイオリン"), "ゔぁいおりん");
+    }
+
+    #[test]
+    fn test_substring_search() {
+        let list = TodoList::from_string("Write REPORT\nread reports\nCall mom").unwrap();
+        let hits = list.search("report");
+        assert_eq!(indices(&hits), [0, 1]);
+        assert!(hits.iter().all(|hit| hit.score == 1.0));
+        assert_eq!(spans(&hits[0]), [(6, 12)]);
+        assert_eq!(list.id_at(0), Some(hits[0].id));
+    }
+
+    #[test]
+    fn test_all_terms_must_match() {
+        let list = TodoList::from_string("Write report\nRead report\nWrite code").unwrap();
+        assert_eq!(indices(&list.search("write report")), [0]);
+        assert_eq!(spans(&list.search("report write")[0]), [(0, 5), (6, 12)]);
+        assert!(list.search("   ").is_empty());
+    }
+
+    #[test]
+    fn test_japanese_search() {
+        let list = TodoList::from_string("週次レポートを書く\nれぽーと確認\nＡＰＩ設計").unwrap();
+        let hits = list.search("レポート");
+        assert_eq!(indices(&hits), [0, 1]);
+        // 「週次」(6 バイト) の後に続く 4 文字
+        assert_eq!(spans(&hits[0]), [(6, 18)]);
+        assert_eq!(indices(&list.search("api")), [2]);
+        assert_eq!(spans(&list.search("api")[0]), [(0, 9)]);
+    }
+
+    #[test]
+    fn test_fuzzy_search() {
+        let list = TodoList::from_string("Schedule meeting\nCall mom\nMeeting notes").unwrap();
+        let hits = list.search("meetnig");
+        assert_eq!(indices(&hits), [0, 2]);
+        assert!(hits[0].score < 1.0 && hits[0].score > 0.0);
+        assert_eq!(spans(&hits[0]), [(9, 16)]);
+
+        assert!(
+            list.search_with("meetnig", &SearchOptions::new().with_fuzzy(false))
+                .is_empty()
+        );
+        assert!(list.search("xyzzy").is_empty());
+    }
+
+    #[test]
+    fn test_ranking() {
+        let list = TodoList::from_string("Buy bred\nBuy bread\nBuy breed").unwrap();
+        let hits = list.search("bread");
+        assert_eq!(hits[0].index, 1);
+        assert_eq!(hits[0].score, 1.0);
+        assert_eq!(indices(&hits[1..]), [0, 2]);
+    }
+
+    #[test]
+    fn test_max_typos() {
+        let list = TodoList::from_string("Deploy").unwrap();
+        assert!(list.search("dplyo").is_empty());
+        let options = SearchOptions::new().with_max_typos(3);
+        assert_eq!(indices(&list.search_with("dplyo", &options)), [0]);
+    }
+
+    #[test]
+    fn test_merge_ranges() {
+        assert_eq!(merge_ranges(vec![5..8, 0..3, 2..4, 8..9]), [0..4, 5..9]);
+    }
+}
@@ -0,0 +1,332 @@
+//! `serde` フィーチャーによるシリアライズ
+
+use crate::parser::{parse_date, parse_line};
+use crate::{Priority, Todo, TodoList};
+use chrono::NaiveDate;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+/// シリアライズ用の `Todo` の表現
+#[derive(Serialize, Deserialize)]
+struct TodoRepr {
+    #[serde(default)]
+    completed: bool,
+    #[serde(default)]
+    priority: Option<Priority>,
+    #[serde(default, with = "optional_date")]
+    completion_date: Option<NaiveDate>,
+    #[serde(default, with = "optional_date")]
+    creation_date: Option<NaiveDate>,
+    description: String,
+    #[serde(default)]
+    contexts: Vec<String>,
+    #[serde(default)]
+    projects: Vec<String>,
+    #[serde(default)]
+    tags: BTreeMap<String, String>,
+}
+
+impl TodoRepr {
+    /// todo.txt の 1 行として書ける内容かチェック
+    fn validate(&self) -> Result<(), String> {
+        if self.description.contains(['\n', '\r']) {
+            return Err(format!("説明文に改行は使えません: {:?}", self.description));
+        }
+        if self.description.trim().is_empty()
+            && self.projects.is_empty()
+            && self.contexts.is_empty()
+        {
+            return Err("タスクの内容が空です".to_string());
+        }
+
+        let words = self
+            .projects
+            .iter()
+            .map(|project| ("プロジェクト", project))
+            .chain(
+                self.contexts
+                    .iter()
+                    .map(|context| ("コンテキスト", context)),
+            )
+            .chain(
+                self.tags
+                    .iter()
+                    .flat_map(|(key, value)| [("タグのキー", key), ("タグの値", value)]),
+            );
+        for (kind, word) in words {
+            if word.is_empty() || word.contains(char::is_whitespace) {
+                return Err(format!(
+                    "{}は空白を含まない文字列である必要があります: {:?}",
+                    kind, word
+                ));
+            }
+        }
+        if let Some(key) = self.tags.keys().find(|key| key.contains(':')) {
+            return Err(format!("タグのキーに `:` は使えません: {:?}", key));
+        }
+        Ok(())
+    }
+}
+
+/// `Todo` は次の形のオブジェクトとしてシリアライズされる。
+///
+/// ```json
+/// {
+///   "completed": false,
+///   "priority": "A",
+///   "completion_date": null,
+///   "creation_date": "2024-01-15",
+///   "description": "Call mom",
+///   "contexts": ["phone"],
+///   "projects": ["Family"],
+///   "tags": {"due": "2024-01-20"}
+/// }
+/// ```
+///
+/// 日付は `YYYY-MM-DD` の文字列、優先度は 1 文字の英大文字で、値がない場合は `null`。
+/// デシリアライズでは `description` 以外のフィールドを省略できる。
+/// 説明文が改行を含むものや、プロジェクト・コンテキスト・タグが空または空白を含むもの、
+/// 説明文が `x ` や `(A)`、日付で始まるものや `+word` / `@word` / `key:value` を含むものなど、
+/// todo.txt の 1 行として書くと同じタスクに読み直せない値はエラーになる。
+/// [`TodoList`] はタスクの配列として扱われる（パースできずに保持している行は含まれない）。
+/// todo.txt の 1 行の文字列として扱いたい場合は [`line`](mod@line) モジュールを使う。
+impl Serialize for Todo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TodoRepr {
+            completed: self.completed,
+            priority: self.priority,
+            completion_date: self.completion_date,
+            creation_date: self.creation_date,
+            description: self.description.clone(),
+            contexts: self.contexts.clone(),
+            projects: self.projects.clone(),
+            tags: self.tags.clone().into_iter().collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Todo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = TodoRepr::deserialize(deserializer)?;
+        repr.validate().map_err(D::Error::custom)?;
+        let mut todo = Todo::new(repr.description);
+        todo.completed = repr.completed;
+        todo.priority = repr.priority;
+        todo.completion_date = repr.completion_date;
+        todo.creation_date = repr.creation_date;
+        todo.contexts = repr.contexts;
+        todo.projects = repr.projects;
+        todo.tags = repr.tags.into_iter().collect();
+
+        // 説明文の `x ` や `(A)`、`+word` などは、書き出した行を読み直すと別のフィールドになる
+        // （完了したタスクの優先度と未完了のタスクの完了日は、行に書かれないだけなので比べない）
+        let line = todo.to_string();
+        let mut expected = todo.clone();
+        if expected.completed {
+            expected.priority = None;
+        } else {
+            expected.completion_date = None;
+        }
+        match parse_line(&line) {
+            Ok(parsed) if parsed == expected => Ok(todo),
+            _ => Err(D::Error::custom(format!(
+                "todo.txt の行として読み直すと別のタスクになります: {}",
+                line
+            ))),
+        }
+    }
+}
+
+impl Serialize for Priority {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.as_char())
+    }
+}
+
+impl<'de> Deserialize<'de> for Priority {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Priority::new(c),
+            _ => None,
+        }
+        .ok_or_else(|| D::Error::custom(format!("優先度は A-Z である必要があります: {}", value)))
+    }
+}
+
+impl Serialize for TodoList {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for TodoList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<Todo>::deserialize(deserializer).map(TodoList::from_iter)
+    }
+}
+
+/// `Option<NaiveDate>` を `YYYY-MM-DD` の文字列として扱う
+mod optional_date {
+    use super::*;
+
+    pub(super) fn serialize<S: Serializer>(
+        date: &Option<NaiveDate>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match date {
+            Some(date) => serializer.collect_str(&date.format("%Y-%m-%d")),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<NaiveDate>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| parse_date(&value).map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+/// [`Todo`] を todo.txt の 1 行の文字列としてシリアライズする
+///
+/// `#[serde(with = "todo_rs::line")]` として使う。
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use todo_rs::Todo;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Message {
+///     #[serde(with = "todo_rs::line")]
+///     task: Todo,
+/// }
+///
+/// let message = Message { task: "(A) Call mom @phone".parse().unwrap() };
+/// let json = serde_json::to_string(&message).unwrap();
+/// assert_eq!(json, r#"{"task":"(A) Call mom @phone"}"#);
+/// ```
+pub mod line {
+    use super::*;
+
+    /// todo.txt の 1 行としてシリアライズ
+    pub fn serialize<S: Serializer>(todo: &Todo, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(todo)
+    }
+
+    /// todo.txt の 1 行からデシリアライズ
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Todo, D::Error> {
+        let line = String::deserialize(deserializer)?;
+        line.parse().map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_todo_schema() {
+        let todo: Todo = "x 2024-01-20 2024-01-15 Call mom +Family @phone due:2024-01-20"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&todo).unwrap(),
+            json!({
+                "completed": true,
+                "priority": null,
+                "completion_date": "2024-01-20",
+                "creation_date": "2024-01-15",
+                "description": "Call mom",
+                "contexts": ["phone"],
+                "projects": ["Family"],
+                "tags": {"due": "2024-01-20"},
+            })
+        );
+    }
+
+    #[test]
+    fn test_todo_round_trip() {
+        let todo: Todo = "(B) 2024-01-15 Write docs +Work @office est:2 due:2024-02-01"
+            .parse()
+            .unwrap();
+        let json = serde_json::to_string(&todo).unwrap();
+        let back: Todo = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.priority, Priority::new('B'));
+        assert_eq!(back.get_tag("est"), Some(&"2".to_string()));
+        // 元の行がないので、タグはキーの順に並ぶ
+        assert_eq!(
+            back.to_string(),
+            "(B) 2024-01-15 Write docs +Work @office due:2024-02-01 est:2"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_defaults() {
+        let todo: Todo = serde_json::from_str(r#"{"description": "Buy milk"}"#).unwrap();
+        assert_eq!(todo, Todo::new("Buy milk"));
+        assert!(serde_json::from_str::<Todo>("{}").is_err());
+    }
+
+    #[test]
+    fn test_deserialize_invalid_values() {
+        let invalid = [
+            json!({"description": "A", "priority": "a"}),
+            json!({"description": "A", "priority": "AB"}),
+            json!({"description": "A", "creation_date": "2024/01/15"}),
+            json!({"description": "a\nb"}),
+            json!({"description": ""}),
+            json!({"description": "A", "projects": ["two words"]}),
+            json!({"description": "A", "contexts": [""]}),
+            json!({"description": "A", "tags": {"est": "1 h"}}),
+            json!({"description": "x done"}),
+            json!({"description": "(A) foo"}),
+            json!({"description": "2024-01-01 foo"}),
+            json!({"description": "buy +milk due:2024-01-01"}),
+        ];
+        for value in invalid {
+            assert!(
+                serde_json::from_value::<Todo>(value.clone()).is_err(),
+                "{value}"
+            );
+        }
+
+        // 説明文がなくても、プロジェクトやコンテキストがあれば todo.txt の行として書ける
+        let todo: Todo =
+            serde_json::from_value(json!({"description": "", "projects": ["Work"]})).unwrap();
+        assert_eq!(todo.to_string(), "+Work");
+    }
+
+    #[test]
+    fn test_list_as_sequence() {
+        let list = TodoList::from_string("(A) First\nx Second").unwrap();
+        let value = serde_json::to_value(&list).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 2);
+        assert_eq!(value[0]["priority"], "A");
+
+        let back: TodoList = serde_json::from_value(value).unwrap();
+        assert_eq!(back.to_string(), list.to_string());
+    }
+
+    #[test]
+    fn test_line_mode() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Wrapper {
+            #[serde(with = "line")]
+            task: Todo,
+        }
+
+        let wrapper: Wrapper = serde_json::from_str(r#"{"task": "(A) Call mom @phone"}"#).unwrap();
+        assert_eq!(wrapper.task.priority, Priority::new('A'));
+        assert_eq!(
+            serde_json::to_string(&wrapper).unwrap(),
+            r#"{"task":"(A) Call mom @phone"}"#
+        );
+        assert!(serde_json::from_str::<Wrapper>(r#"{"task": ""}"#).is_err());
+    }
+}
@@ -0,0 +1,500 @@
+use crate::parser::parse_date;
+use crate::search::fold;
+use crate::{Priority, Result, Todo, TodoError, TodoList};
+use chrono::NaiveDate;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// 複数のキーによるソート順の指定
+///
+/// `priority asc, due asc nulls last, project, description ci` のように、
+/// カンマ区切りでキーを優先度の高い順に並べる。各キーには次の修飾子を付けられる。
+///
+/// - `asc` / `desc`: 昇順（既定）・降順
+/// - `nulls first` / `nulls last`: 値がないタスクを先頭・末尾（既定）に置く（昇順・降順に関係しない）
+/// - `ci`: 大文字小文字を区別せずに比較する（文字列のキーのみ）
+/// - `locale`: 大文字小文字に加えてアクセント記号・全角半角・カタカナとひらがなの違いを無視し、
+///   それでも等しい場合だけ元の文字列で比較する（文字列のキーのみ）。
+///   言語ごとの照合規則には従わない
+/// - `num` / `date` / `text`: タグの値の型（`tag:key` のみ、既定は `text`）
+///
+/// キーと修飾子の大文字小文字は区別しない（`tag:` に続くタグのキーは区別する）。
+/// キーには `priority`・`created`・`completed`・`due`・`threshold`・`done`・
+/// `description`・`project`・`context`・`tag:<key>` を指定できる。
+/// ソートは安定で、すべてのキーが等しいタスクは元の順序を保つ。
+///
+/// ```
+/// use todo_rs::{SortSpec, TodoList};
+///
+/// let mut list = TodoList::from_string("b est:10\nA est:9\nc").unwrap();
+/// let spec: SortSpec = "tag:est num desc, description ci".parse().unwrap();
+/// list.sort_by_spec(&spec);
+/// assert_eq!(list.get(0).unwrap().description, "b");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SortSpec {
+    /// 優先度の高い順に並べたキー
+    pub keys: Vec<SortKey>,
+}
+
+/// ソートのキー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortKey {
+    /// 比較する値
+    pub field: SortField,
+    /// 降順かどうか
+    pub descending: bool,
+    /// 値がないタスクを先頭に置くかどうか
+    pub nulls_first: bool,
+    /// 文字列を大文字小文字を区別せずに比較するかどうか
+    pub case_insensitive: bool,
+    /// 文字列をアクセント記号・全角半角・かなの違いを無視して比較するかどうか
+    pub locale_aware: bool,
+}
+
+/// ソートで比較する値
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortField {
+    /// 優先度（A が先）
+    Priority,
+    /// 作成日
+    CreationDate,
+    /// 完了日
+    CompletionDate,
+    /// 期限日 (`due:`)
+    DueDate,
+    /// 着手可能日 (`t:`)
+    ThresholdDate,
+    /// 完了状態（未完了が先）
+    Completed,
+    /// 説明文
+    Description,
+    /// 最初のプロジェクト
+    Project,
+    /// 最初のコンテキスト
+    Context,
+    /// タグの値
+    Tag(String, TagKind),
+}
+
+/// タグの値の型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TagKind {
+    /// 文字列
+    #[default]
+    Text,
+    /// 数値
+    Number,
+    /// 日付 (YYYY-MM-DD)
+    Date,
+}
+
+impl SortSpec {
+    /// 空のソート指定を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// キーを追加
+    pub fn then(mut self, key: SortKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// 2 つのタスクを比較
+    pub fn compare(&self, a: &Todo, b: &Todo) -> Ordering {
+        self.keys
+            .iter()
+            .map(|key| key.compare(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl SortKey {
+    /// 昇順・値なしは末尾のキーを作成
+    pub fn new(field: SortField) -> Self {
+        Self {
+            field,
+            descending: false,
+            nulls_first: false,
+            case_insensitive: false,
+            locale_aware: false,
+        }
+    }
+
+    /// 降順にする
+    pub fn descending(mut self) -> Self {
+        self.descending = true;
+        self
+    }
+
+    /// 値がないタスクを先頭に置く
+    pub fn nulls_first(mut self) -> Self {
+        self.nulls_first = true;
+        self
+    }
+
+    /// 大文字小文字を区別せずに比較する
+    pub fn case_insensitive(mut self) -> Self {
+        self.case_insensitive = true;
+        self
+    }
+
+    /// アクセント記号・全角半角・かなの違いを無視して比較する
+    pub fn locale_aware(mut self) -> Self {
+        self.locale_aware = true;
+        self
+    }
+
+    /// 2 つのタスクを比較
+    pub fn compare(&self, a: &Todo, b: &Todo) -> Ordering {
+        match (self.value(a), self.value(b)) {
+            (Some(a), Some(b)) => {
+                // 同じキーからは同じ種類の値しか作られず、NaN も除外している
+                let ordering = a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+                if self.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            }
+            (Some(_), None) if self.nulls_first => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) if self.nulls_first => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+
+    fn value(&self, todo: &Todo) -> Option<SortValue> {
+        let text = |s: &str| {
+            Some(if self.locale_aware {
+                SortValue::Collated(fold(s), s.to_string())
+            } else if self.case_insensitive {
+                SortValue::Text(s.to_lowercase())
+            } else {
+                SortValue::Text(s.to_string())
+            })
+        };
+
+        match &self.field {
+            SortField::Priority => todo.priority.map(SortValue::Priority),
+            SortField::CreationDate => todo.creation_date.map(SortValue::Date),
+            SortField::CompletionDate => todo.completion_date.map(SortValue::Date),
+            SortField::DueDate => todo.due_date().ok().flatten().map(SortValue::Date),
+            SortField::ThresholdDate => todo.threshold_date().ok().flatten().map(SortValue::Date),
+            SortField::Completed => Some(SortValue::Flag(todo.completed)),
+            SortField::Description => text(&todo.description),
+            SortField::Project => todo.projects.first().and_then(|p| text(p)),
+            SortField::Context => todo.contexts.first().and_then(|c| text(c)),
+            SortField::Tag(key, kind) => {
+                let value = todo.get_tag(key)?;
+                match kind {
+                    TagKind::Text => text(value),
+                    TagKind::Number => value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|n| !n.is_nan())
+                        .map(SortValue::Number),
+                    TagKind::Date => parse_date(value).ok().map(SortValue::Date),
+                }
+            }
+        }
+    }
+}
+
+/// 比較用の値
+#[derive(Debug, PartialEq, PartialOrd)]
+enum SortValue {
+    Priority(Priority),
+    Date(NaiveDate),
+    Flag(bool),
+    Number(f64),
+    Text(String),
+    /// 畳み込んだ文字列と元の文字列
+    Collated(String, String),
+}
+
+impl FromStr for SortSpec {
+    type Err = TodoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut spec = SortSpec::new();
+        for item in s.split(',') {
+            spec.keys.push(item.parse()?);
+        }
+        Ok(spec)
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = TodoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid =
+            |message: &str| TodoError::InvalidSortSpec(format!("{}: {}", message, s.trim()));
+
+        let mut words = s.split_whitespace();
+        let name = words.next().ok_or_else(|| invalid("キーがありません"))?;
+        let mut field = match name.to_ascii_lowercase().as_str() {
+            "priority" | "pri" => SortField::Priority,
+            "created" => SortField::CreationDate,
+            "completed" => SortField::CompletionDate,
+            "due" => SortField::DueDate,
+            "threshold" | "t" => SortField::ThresholdDate,
+            "done" => SortField::Completed,
+            "description" => SortField::Description,
+            "project" => SortField::Project,
+            "context" => SortField::Context,
+            _ => match name.split_at_checked(4) {
+                Some((prefix, key)) if prefix.eq_ignore_ascii_case("tag:") && !key.is_empty() => {
+                    SortField::Tag(key.to_string(), TagKind::Text)
+                }
+                _ => return Err(invalid("不明なキーです")),
+            },
+        };
+
+        let mut key = SortKey::new(SortField::Priority);
+        while let Some(word) = words.next() {
+            match word.to_ascii_lowercase().as_str() {
+                "asc" => key.descending = false,
+                "desc" => key.descending = true,
+                "ci" => key.case_insensitive = true,
+                "locale" => key.locale_aware = true,
+                "nulls" => {
+                    key.nulls_first = match words.next().map(str::to_ascii_lowercase).as_deref() {
+                        Some("first") => true,
+                        Some("last") => false,
+                        _ => return Err(invalid("`nulls` の後には first か last が必要です")),
+                    }
+                }
+                kind @ ("num" | "date" | "text") => match &mut field {
+                    SortField::Tag(_, tag_kind) => {
+                        *tag_kind = match kind {
+                            "num" => TagKind::Number,
+                            "date" => TagKind::Date,
+                            _ => TagKind::Text,
+                        }
+                    }
+                    _ => return Err(invalid("型はタグのキーにのみ指定できます")),
+                },
+                _ => return Err(invalid("不明な修飾子です")),
+            }
+        }
+
+        key.field = field;
+        Ok(key)
+    }
+}
+
+impl fmt::Display for SortSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, key) in self.keys.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", key)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.field {
+            SortField::Priority => write!(f, "priority")?,
+            SortField::CreationDate => write!(f, "created")?,
+            SortField::CompletionDate => write!(f, "completed")?,
+            SortField::DueDate => write!(f, "due")?,
+            SortField::ThresholdDate => write!(f, "threshold")?,
+            SortField::Completed => write!(f, "done")?,
+            SortField::Description => write!(f, "description")?,
+            SortField::Project => write!(f, "project")?,
+            SortField::Context => write!(f, "context")?,
+            SortField::Tag(key, kind) => {
+                write!(f, "tag:{}", key)?;
+                match kind {
+                    TagKind::Text => {}
+                    TagKind::Number => write!(f, " num")?,
+                    TagKind::Date => write!(f, " date")?,
+                }
+            }
+        }
+        if self.descending {
+            write!(f, " desc")?;
+        }
+        if self.nulls_first {
+            write!(f, " nulls first")?;
+        }
+        if self.case_insensitive {
+            write!(f, " ci")?;
+        }
+        if self.locale_aware {
+            write!(f, " locale")?;
+        }
+        Ok(())
+    }
+}
+
+impl TodoList {
+    /// ソート指定に従ってタスクをソート（安定ソート）
+    pub fn sort_by_spec(&mut self, spec: &SortSpec) {
+        self.sort_by(|a, b| spec.compare(a, b));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptions(list: &TodoList) -> Vec<&str> {
+        list.iter().map(|todo| todo.description.as_str()).collect()
+    }
+
+    fn sorted(content: &str, spec: &str) -> Vec<String> {
+        let mut list = TodoList::from_string(content).unwrap();
+        list.sort_by_spec(&spec.parse().unwrap());
+        descriptions(&list).into_iter().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        let spec: SortSpec = "priority asc, due asc nulls last, project, description ci"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            spec,
+            SortSpec::new()
+                .then(SortKey::new(SortField::Priority))
+                .then(SortKey::new(SortField::DueDate))
+                .then(SortKey::new(SortField::Project))
+                .then(SortKey::new(SortField::Description).case_insensitive())
+        );
+        assert_eq!(spec.to_string(), "priority, due, project, description ci");
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let input = "tag:est num desc nulls first, created desc, done, description locale";
+        let spec: SortSpec = input.parse().unwrap();
+        assert_eq!(spec.to_string(), input);
+        assert_eq!(spec.to_string().parse::<SortSpec>().unwrap(), spec);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for input in [
+            "",
+            "priority,",
+            "size",
+            "due nulls",
+            "due num",
+            "priority up",
+            "tag:",
+        ] {
+            assert!(
+                matches!(
+                    input.parse::<SortSpec>(),
+                    Err(TodoError::InvalidSortSpec(_))
+                ),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_multi_key_sort() {
+        let content = "\
+(B) Beta due:2024-03-02
+(A) Alpha
+(B) Gamma due:2024-03-01
+Delta due:2024-01-01
+(B) Epsilon";
+        assert_eq!(
+            sorted(content, "priority, due"),
+            ["Alpha", "Gamma", "Beta", "Epsilon", "Delta"]
+        );
+        assert_eq!(
+            sorted(content, "priority nulls first, due desc nulls first"),
+            ["Delta", "Alpha", "Epsilon", "Beta", "Gamma"]
+        );
+    }
+
+    #[test]
+    fn test_stable_sort() {
+        let content = "c +X\na +Y\nb +X\nd";
+        assert_eq!(sorted(content, "project"), ["c", "b", "a", "d"]);
+        assert_eq!(sorted(content, "project desc"), ["a", "c", "b", "d"]);
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let content = "banana\nApple\ncherry";
+        assert_eq!(
+            sorted(content, "description"),
+            ["Apple", "banana", "cherry"]
+        );
+        assert_eq!(
+            sorted(content, "description desc"),
+            ["cherry", "banana", "Apple"]
+        );
+
+        let content = "b\nA\nC";
+        assert_eq!(sorted(content, "description"), ["A", "C", "b"]);
+        assert_eq!(sorted(content, "description ci"), ["A", "b", "C"]);
+    }
+
+    #[test]
+    fn test_locale_aware() {
+        let content = "zebra\nÉclair\neclair\nApple";
+        assert_eq!(
+            sorted(content, "description ci"),
+            ["Apple", "eclair", "zebra", "Éclair"]
+        );
+        assert_eq!(
+            sorted(content, "description locale"),
+            ["Apple", "eclair", "Éclair", "zebra"]
+        );
+
+        let content = "ミカン\nりんご\nイチゴ";
+        assert_eq!(
+            sorted(content, "description locale"),
+            ["イチゴ", "ミカン", "りんご"]
+        );
+    }
+
+    #[test]
+    fn test_keyword_case() {
+        let spec: SortSpec = "TAG:Est NUM DESC, Description CI".parse().unwrap();
+        assert_eq!(spec.to_string(), "tag:Est num desc, description ci");
+    }
+
+    #[test]
+    fn test_tag_kinds() {
+        let content =
+            "ten est:10\nnine est:9\nbad est:x\nnone\nlater on:2024-12-01\nsooner on:2024-02-01";
+        assert_eq!(
+            sorted(content, "tag:est num"),
+            ["nine", "ten", "bad", "none", "later", "sooner"]
+        );
+        assert_eq!(
+            sorted(content, "tag:est"),
+            ["ten", "nine", "bad", "none", "later", "sooner"]
+        );
+        assert_eq!(
+            sorted(content, "tag:on date"),
+            ["sooner", "later", "ten", "nine", "bad", "none"]
+        );
+    }
+
+    #[test]
+    fn test_sort_by_spec_undo() {
+        let mut list = TodoList::from_string("b\na").unwrap().with_history(10);
+        list.sort_by_spec(&"description".parse().unwrap());
+        assert_eq!(descriptions(&list), ["a", "b"]);
+        assert!(list.undo().unwrap());
+        assert_eq!(descriptions(&list), ["b", "a"]);
+    }
+}
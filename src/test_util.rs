@@ -0,0 +1,18 @@
+use crate::{Todo, TodoList};
+use std::fs;
+use std::path::PathBuf;
+
+/// テストごとに空の一時ディレクトリを作成
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("todo-rs-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// 2 つのリストのタスクがフィールド単位で一致することを確認（元の行は比べない）
+pub(crate) fn assert_same_todos(actual: &TodoList, expected: &TodoList) {
+    let actual: Vec<&Todo> = actual.iter().collect();
+    let expected: Vec<&Todo> = expected.iter().collect();
+    assert_eq!(actual, expected);
+}
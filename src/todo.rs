@@ -1,11 +1,30 @@
+use crate::clock::{Clock, SystemClock};
+use crate::error::{Result, TodoError};
+use crate::parser::{Token, classify, parse_date, parse_header};
 use crate::priority::Priority;
+use crate::recurrence::Recurrence;
 use chrono::NaiveDate;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::str::FromStr;
 
+/// 期限日を表すタグのキー
+pub(crate) const DUE_KEY: &str = "due";
+
+/// 着手可能日（しきい値日）を表すタグのキー
+pub(crate) const THRESHOLD_KEY: &str = "t";
+
+/// 繰り返し間隔を表すタグのキー
+const RECURRENCE_KEY: &str = "rec";
+
+/// iCalendar の UID を表すタグのキー
+pub(crate) const UID_KEY: &str = "uid";
+
 /// Todo.txt のタスクを表す構造体
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// 比較ではパース元の行を無視し、フィールドの内容だけを比べる。
+#[derive(Debug, Clone)]
 pub struct Todo {
     /// タスクが完了しているかどうか
     pub completed: bool,
@@ -30,6 +49,9 @@ pub struct Todo {
 
     /// 追加のメタデータ (key:value 形式)
     pub tags: HashMap<String, String>,
+
+    /// パース元の行（トークン順序を保ったシリアライズに使用）
+    pub(crate) raw: Option<String>,
 }
 
 impl Todo {
@@ -44,14 +66,30 @@ impl Todo {
             contexts: Vec::new(),
             projects: Vec::new(),
             tags: HashMap::new(),
+            raw: None,
         }
     }
 
     /// タスクを完了としてマーク
+    ///
+    /// 完了日にはシステムのローカル日付が使われる。
+    /// 日付を固定したい場合は [`Todo::complete_with`] を使用する。
     pub fn complete(&mut self) {
+        self.complete_with(&SystemClock);
+    }
+
+    /// クロックの日付を完了日としてタスクを完了としてマーク
+    pub fn complete_with(&mut self, clock: &dyn Clock) {
+        self.complete_on(clock.today());
+    }
+
+    /// 完了日を指定してタスクを完了としてマーク
+    ///
+    /// 完了日がすでに設定されている場合は変更しない。
+    pub fn complete_on(&mut self, date: NaiveDate) {
         self.completed = true;
         if self.completion_date.is_none() {
-            self.completion_date = Some(chrono::Local::now().naive_local().date());
+            self.completion_date = Some(date);
         }
     }
 
@@ -113,66 +151,350 @@ impl Todo {
     pub fn get_tag(&self, key: &str) -> Option<&String> {
         self.tags.get(key)
     }
-}
 
-impl FromStr for Todo {
-    type Err = crate::error::TodoError;
+    /// タグを削除して、その値を返す
+    pub fn remove_tag(&mut self, key: &str) -> Option<String> {
+        self.tags.remove(key)
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        crate::parser::parse_todo(s)
+    /// 日付 (YYYY-MM-DD) として解釈したタグの値を取得
+    ///
+    /// タグがない場合は `None`、日付として解釈できない場合はエラーを返す。
+    pub fn date_tag(&self, key: &str) -> Result<Option<NaiveDate>> {
+        match self.tags.get(key) {
+            Some(value) => parse_date(value)
+                .map(Some)
+                .map_err(|_| TodoError::InvalidDateFormat(format!("{}:{}", key, value))),
+            None => Ok(None),
+        }
     }
-}
 
-impl fmt::Display for Todo {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // 完了マーカー
+    /// 日付 (YYYY-MM-DD) をタグとして設定
+    pub fn set_date_tag(&mut self, key: impl Into<String>, date: NaiveDate) {
+        self.add_tag(key, date.format("%Y-%m-%d").to_string());
+    }
+
+    /// 期限日 (`due:`) を取得
+    pub fn due_date(&self) -> Result<Option<NaiveDate>> {
+        self.date_tag(DUE_KEY)
+    }
+
+    /// 期限日 (`due:`) を設定
+    pub fn set_due_date(&mut self, date: NaiveDate) {
+        self.set_date_tag(DUE_KEY, date);
+    }
+
+    /// 着手可能日 (`t:`) を取得
+    pub fn threshold_date(&self) -> Result<Option<NaiveDate>> {
+        self.date_tag(THRESHOLD_KEY)
+    }
+
+    /// 着手可能日 (`t:`) を設定
+    pub fn set_threshold_date(&mut self, date: NaiveDate) {
+        self.set_date_tag(THRESHOLD_KEY, date);
+    }
+
+    /// 外部形式の日付フィールドに書き出す期限日と着手可能日
+    ///
+    /// 日付として読めない値は `None` になり、[`Todo::export_tags`] でタグのまま書き出す。
+    pub(crate) fn export_dates(&self) -> (Option<NaiveDate>, Option<NaiveDate>) {
+        (
+            self.due_date().ok().flatten(),
+            self.threshold_date().ok().flatten(),
+        )
+    }
+
+    /// 日付フィールドに書き出した `due:` / `t:` を除いたタグをキー順に返す
+    pub(crate) fn export_tags(&self) -> Vec<(&String, &String)> {
+        let (due, threshold) = self.export_dates();
+        let mut tags: Vec<(&String, &String)> = self
+            .tags
+            .iter()
+            .filter(|(key, _)| match key.as_str() {
+                DUE_KEY => due.is_none(),
+                THRESHOLD_KEY => threshold.is_none(),
+                _ => true,
+            })
+            .collect();
+        tags.sort();
+        tags
+    }
+
+    /// 未完了のタスクから完了日を取り除く
+    ///
+    /// 完了日は完了したタスクにしか書けないため、外部形式から読み込んだタスクの整合性を取る。
+    pub(crate) fn normalize_completion(&mut self) {
+        if !self.completed {
+            self.completion_date = None;
+        }
+    }
+
+    /// 指定日に着手可能かチェック
+    ///
+    /// 未完了で、着手可能日が指定日以前（または未設定）の場合に `true` を返す。
+    /// 着手可能日が日付として解釈できない場合は、隠さずに着手可能として扱う。
+    pub fn is_actionable(&self, today: NaiveDate) -> bool {
+        !self.completed && !matches!(self.threshold_date(), Ok(Some(date)) if date > today)
+    }
+
+    /// 繰り返し間隔 (`rec:`) を取得
+    pub fn recurrence(&self) -> Result<Option<Recurrence>> {
+        self.tags
+            .get(RECURRENCE_KEY)
+            .map(|value| value.parse())
+            .transpose()
+    }
+
+    /// 繰り返しタスクを `completed_on` に完了したときの、次のタスクを作成
+    ///
+    /// 繰り返し指定がない場合は `None` を返す。
+    /// 新しいタスクは未完了で作成日が `completed_on` になり、
+    /// 期限日 (`due:`) と着手可能日 (`t:`) は繰り返し間隔に従って進められる。
+    /// 通常モードでは完了日を基準に期限日を決め、着手可能日は元の期限日との間隔を保つ。
+    /// 厳密モードではそれぞれの日付を元の値から 1 間隔分進める。
+    /// タスクを識別する `id:` と `uid:` は引き継がない。
+    pub fn next_occurrence(&self, completed_on: NaiveDate) -> Result<Option<Todo>> {
+        let Some(recurrence) = self.recurrence()? else {
+            return Ok(None);
+        };
+        let due = self.due_date()?;
+        let threshold = self.threshold_date()?;
+        let out_of_range = || TodoError::InvalidRecurrence(recurrence.to_string());
+
+        let (next_due, next_threshold) = if recurrence.strict {
+            let advance = |date: Option<NaiveDate>| {
+                date.map(|date| recurrence.advance(date).ok_or_else(out_of_range))
+                    .transpose()
+            };
+            (advance(due)?, advance(threshold)?)
+        } else {
+            let base = recurrence.advance(completed_on).ok_or_else(out_of_range)?;
+            match (due, threshold) {
+                (Some(due), Some(threshold)) => {
+                    let next_threshold = base
+                        .checked_sub_signed(due - threshold)
+                        .ok_or_else(out_of_range)?;
+                    (Some(base), Some(next_threshold))
+                }
+                (Some(_), None) => (Some(base), None),
+                (None, Some(_)) => (None, Some(base)),
+                (None, None) => (None, None),
+            }
+        };
+
+        let mut next = self.clone();
+        next.completed = false;
+        next.completion_date = None;
+        next.creation_date = Some(completed_on);
+        next.remove_tag(crate::id::ID_KEY);
+        next.remove_tag(UID_KEY);
+        if let Some(date) = next_due {
+            next.set_due_date(date);
+        }
+        if let Some(date) = next_threshold {
+            next.set_threshold_date(date);
+        }
+
+        Ok(Some(next))
+    }
+
+    /// 永続的な ID (`id:` タグ) を取得
+    pub fn persistent_id(&self) -> Option<&str> {
+        self.tags.get(crate::id::ID_KEY).map(String::as_str)
+    }
+
+    /// パース元の行を取得（`Todo::new` で作成した場合は `None`）
+    pub fn original_line(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+
+    /// 完了マーカー・日付・優先度のトークンを生成
+    fn header_tokens(&self) -> Vec<Cow<'_, str>> {
+        let mut tokens = Vec::new();
+
         if self.completed {
-            write!(f, "x")?;
+            tokens.push(Cow::Borrowed("x"));
 
             // 完了日
             if let Some(date) = self.completion_date {
-                write!(f, " {}", date.format("%Y-%m-%d"))?;
+                tokens.push(Cow::Owned(date.format("%Y-%m-%d").to_string()));
             }
+        } else if let Some(priority) = self.priority {
+            // 優先度（未完了の場合のみ）
+            tokens.push(Cow::Owned(priority.to_string()));
+        }
 
-            // 作成日
-            if let Some(date) = self.creation_date {
-                write!(f, " {}", date.format("%Y-%m-%d"))?;
-            }
+        // 作成日
+        if let Some(date) = self.creation_date {
+            tokens.push(Cow::Owned(date.format("%Y-%m-%d").to_string()));
+        }
 
-            write!(f, " ")?;
-        } else {
-            // 優先度（未完了の場合のみ）
-            if let Some(priority) = self.priority {
-                write!(f, "{} ", priority)?;
+        tokens
+    }
+
+    /// 説明・プロジェクト・コンテキスト・タグを既定の順序で並べる
+    fn body_tokens(&self) -> Vec<Cow<'_, str>> {
+        let mut tokens = Vec::new();
+
+        if !self.description.is_empty() {
+            tokens.push(Cow::Borrowed(self.description.as_str()));
+        }
+
+        let projects: Vec<&String> = self.projects.iter().collect();
+        let contexts: Vec<&String> = self.contexts.iter().collect();
+        self.push_remaining(&mut tokens, &projects, &contexts, &HashSet::new());
+
+        tokens
+    }
+
+    /// パース元の行のトークン順序を保ったまま、現在の内容を並べる
+    ///
+    /// 削除された要素は取り除き、値が変わったタグはその位置で書き換え、
+    /// 新しく追加された要素は末尾に追加する。
+    fn body_tokens_from<'a>(&'a self, raw: &'a str) -> Vec<Cow<'a, str>> {
+        let mut parts = raw.split_whitespace().peekable();
+        parse_header(&mut Todo::new(""), &mut parts);
+        let parts: Vec<(&str, Token<'_>)> = parts.map(|part| (part, classify(part))).collect();
+
+        let words: Vec<&str> = parts
+            .iter()
+            .filter_map(|(_, token)| match token {
+                Token::Word(word) => Some(*word),
+                _ => None,
+            })
+            .collect();
+        let description_unchanged = words.join(" ") == self.description;
+
+        // 同じキーのタグが複数ある場合、パース時に採用されるのは最後のもの
+        let mut last_tag = HashMap::new();
+        for (i, (_, token)) in parts.iter().enumerate() {
+            if let Token::Tag(key, _) = token {
+                last_tag.insert(*key, i);
             }
+        }
 
-            // 作成日
-            if let Some(date) = self.creation_date {
-                write!(f, "{} ", date.format("%Y-%m-%d"))?;
+        let mut projects: Vec<&String> = self.projects.iter().collect();
+        let mut contexts: Vec<&String> = self.contexts.iter().collect();
+        let mut written_tags = HashSet::new();
+        let mut description_written = description_unchanged;
+        let mut tokens = Vec::new();
+
+        for (i, (part, token)) in parts.iter().enumerate() {
+            match token {
+                Token::Word(word) => {
+                    if description_unchanged {
+                        tokens.push(Cow::Borrowed(*word));
+                    } else if !description_written {
+                        // 説明が変更された場合は最初の単語の位置に書き込む
+                        if !self.description.is_empty() {
+                            tokens.push(Cow::Borrowed(self.description.as_str()));
+                        }
+                        description_written = true;
+                    }
+                }
+                Token::Project(name) => {
+                    if let Some(pos) = projects.iter().position(|p| p == name) {
+                        projects.remove(pos);
+                        tokens.push(Cow::Borrowed(*part));
+                    }
+                }
+                Token::Context(name) => {
+                    if let Some(pos) = contexts.iter().position(|c| c == name) {
+                        contexts.remove(pos);
+                        tokens.push(Cow::Borrowed(*part));
+                    }
+                }
+                Token::Tag(key, value) => {
+                    let Some(current) = self.tags.get(*key) else {
+                        continue;
+                    };
+                    if last_tag[key] != i || current == value {
+                        tokens.push(Cow::Borrowed(*part));
+                    } else {
+                        tokens.push(Cow::Owned(format!("{}:{}", key, current)));
+                    }
+                    written_tags.insert(*key);
+                }
             }
         }
 
-        // 説明
-        write!(f, "{}", self.description)?;
+        if !description_written && !self.description.is_empty() {
+            tokens.insert(0, Cow::Borrowed(self.description.as_str()));
+        }
+
+        self.push_remaining(&mut tokens, &projects, &contexts, &written_tags);
+
+        tokens
+    }
 
-        // プロジェクト
-        for project in &self.projects {
-            write!(f, " +{}", project)?;
+    /// プロジェクト、コンテキスト、書き出し済みでないタグ（キー順）を追加
+    fn push_remaining(
+        &self,
+        tokens: &mut Vec<Cow<'_, str>>,
+        projects: &[&String],
+        contexts: &[&String],
+        written_tags: &HashSet<&str>,
+    ) {
+        for project in projects {
+            tokens.push(Cow::Owned(format!("+{}", project)));
         }
 
-        // コンテキスト
-        for context in &self.contexts {
-            write!(f, " @{}", context)?;
+        for context in contexts {
+            tokens.push(Cow::Owned(format!("@{}", context)));
         }
 
-        // タグ（key:value）
-        let mut tags: Vec<_> = self.tags.iter().collect();
+        let mut tags: Vec<_> = self
+            .tags
+            .iter()
+            .filter(|(key, _)| !written_tags.contains(key.as_str()))
+            .collect();
         tags.sort_by_key(|(k, _)| *k);
         for (key, value) in tags {
-            write!(f, " {}:{}", key, value)?;
+            tokens.push(Cow::Owned(format!("{}:{}", key, value)));
         }
+    }
+}
+
+impl FromStr for Todo {
+    type Err = TodoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        crate::parser::parse_todo(s)
+    }
+}
+
+impl PartialEq for Todo {
+    fn eq(&self, other: &Self) -> bool {
+        self.completed == other.completed
+            && self.priority == other.priority
+            && self.completion_date == other.completion_date
+            && self.creation_date == other.creation_date
+            && self.description == other.description
+            && self.contexts == other.contexts
+            && self.projects == other.projects
+            && self.tags == other.tags
+    }
+}
+
+impl Eq for Todo {}
 
-        Ok(())
+impl fmt::Display for Todo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tokens = self.header_tokens();
+        match &self.raw {
+            Some(raw) => tokens.extend(self.body_tokens_from(raw)),
+            None => tokens.extend(self.body_tokens()),
+        }
+        let line = tokens.join(" ");
+
+        // 内容が変わっていなければ元の行をそのまま出力する
+        if let Some(raw) = &self.raw
+            && raw.split_whitespace().eq(line.split(' '))
+        {
+            return write!(f, "{}", raw);
+        }
+
+        write!(f, "{}", line)
     }
 }
 
@@ -196,6 +518,14 @@ mod tests {
         assert!(todo.completion_date.is_some());
     }
 
+    #[test]
+    fn test_complete_with_clock() {
+        let date = NaiveDate::from_ymd_opt(2024, 11, 3).unwrap();
+        let mut todo = Todo::new("Test task");
+        todo.complete_with(&crate::FixedClock::new(date));
+        assert_eq!(todo.completion_date, Some(date));
+    }
+
     #[test]
     fn test_add_context_and_project() {
         let mut todo = Todo::new("Test task");
@@ -214,6 +544,110 @@ mod tests {
         assert_eq!(todo.priority, Some(priority));
     }
 
+    #[test]
+    fn test_due_date() {
+        let mut todo: Todo = "Submit report due:2024-11-10".parse().unwrap();
+        assert_eq!(
+            todo.due_date().unwrap(),
+            NaiveDate::from_ymd_opt(2024, 11, 10)
+        );
+
+        todo.set_due_date(NaiveDate::from_ymd_opt(2024, 11, 12).unwrap());
+        assert_eq!(todo.to_string(), "Submit report due:2024-11-12");
+
+        todo.remove_tag("due");
+        assert_eq!(todo.due_date().unwrap(), None);
+    }
+
+    #[test]
+    fn test_invalid_due_date() {
+        let todo: Todo = "Submit report due:tomorrow".parse().unwrap();
+        assert!(matches!(
+            todo.due_date(),
+            Err(TodoError::InvalidDateFormat(value)) if value == "due:tomorrow"
+        ));
+    }
+
+    #[test]
+    fn test_threshold_date() {
+        let today = NaiveDate::from_ymd_opt(2024, 11, 5).unwrap();
+        let mut todo: Todo = "Plan trip t:2024-11-06".parse().unwrap();
+        assert_eq!(
+            todo.threshold_date().unwrap(),
+            NaiveDate::from_ymd_opt(2024, 11, 6)
+        );
+        assert!(!todo.is_actionable(today));
+
+        todo.set_threshold_date(today);
+        assert!(todo.is_actionable(today));
+
+        todo.complete();
+        assert!(!todo.is_actionable(today));
+    }
+
+    #[test]
+    fn test_invalid_threshold_is_actionable() {
+        let today = NaiveDate::from_ymd_opt(2024, 11, 5).unwrap();
+        let todo: Todo = "Plan trip t:later".parse().unwrap();
+        assert!(todo.threshold_date().is_err());
+        assert!(todo.is_actionable(today));
+    }
+
+    #[test]
+    fn test_next_occurrence_from_completion() {
+        let todo: Todo =
+            "(A) 2024-11-01 Water plants +Home @garden due:2024-11-05 t:2024-11-03 rec:1w"
+                .parse()
+                .unwrap();
+        let completed_on = NaiveDate::from_ymd_opt(2024, 11, 7).unwrap();
+        let next = todo.next_occurrence(completed_on).unwrap().unwrap();
+
+        assert_eq!(
+            next.to_string(),
+            "(A) 2024-11-07 Water plants +Home @garden due:2024-11-14 t:2024-11-12 rec:1w"
+        );
+    }
+
+    #[test]
+    fn test_next_occurrence_strict() {
+        let todo: Todo = "Pay rent due:2024-01-31 rec:+1m".parse().unwrap();
+        let completed_on = NaiveDate::from_ymd_opt(2024, 2, 3).unwrap();
+        let next = todo.next_occurrence(completed_on).unwrap().unwrap();
+
+        assert_eq!(
+            next.due_date().unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 29)
+        );
+        assert_eq!(next.creation_date, Some(completed_on));
+        assert!(!next.completed);
+    }
+
+    #[test]
+    fn test_next_occurrence_drops_identity_tags() {
+        let todo: Todo = "Water plants id:7 uid:abc@example.com parent:3 rec:1w"
+            .parse()
+            .unwrap();
+        let completed_on = NaiveDate::from_ymd_opt(2024, 11, 7).unwrap();
+        let next = todo.next_occurrence(completed_on).unwrap().unwrap();
+
+        assert!(next.persistent_id().is_none());
+        assert!(!next.has_tag("uid"));
+        assert_eq!(next.get_tag("parent").map(String::as_str), Some("3"));
+    }
+
+    #[test]
+    fn test_next_occurrence_without_recurrence() {
+        let completed_on = NaiveDate::from_ymd_opt(2024, 2, 3).unwrap();
+        let todo: Todo = "Pay rent due:2024-01-31".parse().unwrap();
+        assert!(todo.next_occurrence(completed_on).unwrap().is_none());
+
+        let todo: Todo = "Pay rent rec:sometimes".parse().unwrap();
+        assert!(matches!(
+            todo.next_occurrence(completed_on),
+            Err(TodoError::InvalidRecurrence(_))
+        ));
+    }
+
     #[test]
     fn test_display_simple_todo() {
         let todo = Todo::new("Call Mom");
@@ -261,14 +695,76 @@ mod tests {
     fn test_roundtrip_parse_and_display() {
         let original = "(A) 2024-11-01 Call Mom +Family @phone due:2024-11-10";
         let todo: Todo = original.parse().unwrap();
-        let serialized = todo.to_string();
-
-        // パースして再シリアライズした結果が元と一致する（順序は異なる可能性がある）
-        assert!(serialized.contains("(A)"));
-        assert!(serialized.contains("2024-11-01"));
-        assert!(serialized.contains("Call Mom"));
-        assert!(serialized.contains("+Family"));
-        assert!(serialized.contains("@phone"));
-        assert!(serialized.contains("due:2024-11-10"));
+        assert_eq!(todo.to_string(), original);
+    }
+
+    #[test]
+    fn test_roundtrip_preserves_token_order() {
+        let original = "Call +Mom about @phone   bill due:2024-11-10 soon";
+        let todo: Todo = original.parse().unwrap();
+        assert_eq!(todo.to_string(), original);
+    }
+
+    #[test]
+    fn test_edit_keeps_inline_positions() {
+        let mut todo: Todo = "Call +Mom about @phone bill due:2024-11-10 soon"
+            .parse()
+            .unwrap();
+        todo.add_tag("due", "2024-11-12");
+        todo.add_context("home");
+        todo.priority = Priority::new('B');
+        assert_eq!(
+            todo.to_string(),
+            "(B) Call +Mom about @phone bill due:2024-11-12 soon @home"
+        );
+    }
+
+    #[test]
+    fn test_edit_removed_and_rewritten_tokens() {
+        let mut todo: Todo = "Call +Mom about @phone bill".parse().unwrap();
+        todo.projects.clear();
+        todo.description = "Text Dad".to_string();
+        assert_eq!(todo.to_string(), "Text Dad @phone");
+    }
+
+    #[test]
+    fn test_complete_parsed_todo() {
+        let mut todo: Todo = "2024-11-01 Call +Mom about @phone".parse().unwrap();
+        todo.completion_date = NaiveDate::from_ymd_opt(2024, 11, 3);
+        todo.completed = true;
+        assert_eq!(
+            todo.to_string(),
+            "x 2024-11-03 2024-11-01 Call +Mom about @phone"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_tag_keys_roundtrip() {
+        let original = "Task due:2024-01-01 due:2024-01-02";
+        let mut todo: Todo = original.parse().unwrap();
+        assert_eq!(todo.to_string(), original);
+
+        todo.add_tag("due", "2024-01-03");
+        assert_eq!(todo.to_string(), "Task due:2024-01-01 due:2024-01-03");
+    }
+
+    #[test]
+    fn test_eq_ignores_original_line() {
+        let parsed: Todo = "(A) 2024-11-01 Call @phone +Mom due:2024-11-08"
+            .parse()
+            .unwrap();
+        let mut built = Todo::new("Call")
+            .with_priority(Priority::new('A').unwrap())
+            .with_creation_date(NaiveDate::from_ymd_opt(2024, 11, 1).unwrap());
+        built.add_project("Mom");
+        built.add_context("phone");
+        built.add_tag("due", "2024-11-08");
+        assert_eq!(parsed, built);
+
+        let reordered: Todo = "(A) 2024-11-01 Call +Mom due:2024-11-08 @phone"
+            .parse()
+            .unwrap();
+        assert_eq!(parsed, reordered);
+        assert_ne!(parsed.to_string(), reordered.to_string());
     }
 }